@@ -0,0 +1,120 @@
+use crate::vector::{Float3, Scalar};
+use crate::support_map::SupportMap;
+
+// region Aabb
+
+/// Axis aligned bounding box, empty when `min` exceeds `max` on any axis.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb<S = f32> {
+    pub min: Float3<S>,
+    pub max: Float3<S>,
+}
+
+impl<S> Aabb<S> where S: Scalar {
+    pub fn new(min_:Float3<S>, max_:Float3<S>) -> Aabb<S> {
+        Aabb {min:min_, max:max_}
+    }
+
+    /// box containing nothing, the identity of `merged`
+    pub fn empty() -> Aabb<S> {
+        Aabb {min:Float3 {x:S::MAX, y:S::MAX, z:S::MAX}, max:Float3 {x:S::MIN, y:S::MIN, z:S::MIN}}
+    }
+
+    /// tight box of a shape from its support points along the six axis directions
+    pub fn from_support<A>(poly_:&A) -> Aabb<S> where A: SupportMap<Float3<S>> + ?Sized {
+        let (zero, one) = (S::ZERO, S::ONE);
+        let mut ret = Aabb::empty();
+        for (k, d) in [Float3 {x:one, y:zero, z:zero}, Float3 {x:zero, y:one, z:zero}, Float3 {x:zero, y:zero, z:one}].iter().enumerate() {
+            set_axis(&mut ret.max, k, poly_.support(d).0.dot(d));
+            set_axis(&mut ret.min, k, poly_.support(&-d).0.dot(d));
+        }
+        ret
+    }
+
+    pub fn from_points(point_:&[Float3<S>]) -> Aabb<S> {
+        point_.iter().fold(Aabb::empty(), |b, p| b.merged(&Aabb {min:*p, max:*p}))
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.min.x > self.max.x || self.min.y > self.max.y || self.min.z > self.max.z
+    }
+
+    /// whether the boxes share a point, touching boxes included
+    pub fn overlaps(&self, b_:&Aabb<S>) -> bool {
+        self.min.x <= b_.max.x && b_.min.x <= self.max.x
+            && self.min.y <= b_.max.y && b_.min.y <= self.max.y
+            && self.min.z <= b_.max.z && b_.min.z <= self.max.z
+    }
+
+    pub fn contains(&self, b_:&Aabb<S>) -> bool {
+        self.min.x <= b_.min.x && self.min.y <= b_.min.y && self.min.z <= b_.min.z
+            && b_.max.x <= self.max.x && b_.max.y <= self.max.y && b_.max.z <= self.max.z
+    }
+
+    /// smallest box containing both boxes
+    pub fn merged(&self, b_:&Aabb<S>) -> Aabb<S> {
+        Aabb {
+            min: Float3 {x:self.min.x.min(b_.min.x), y:self.min.y.min(b_.min.y), z:self.min.z.min(b_.min.z)},
+            max: Float3 {x:self.max.x.max(b_.max.x), y:self.max.y.max(b_.max.y), z:self.max.z.max(b_.max.z)},
+        }
+    }
+
+    /// box grown by `margin_` on every side
+    pub fn expanded(&self, margin_:S) -> Aabb<S> {
+        let m = Float3 {x:margin_, y:margin_, z:margin_};
+        Aabb {min:self.min - m, max:self.max + m}
+    }
+
+    pub fn center(&self) -> Float3<S> {
+        (self.min + self.max).scaled(S::ONE, S::from_f32(2.0))
+    }
+
+    pub fn extent(&self) -> Float3<S> {
+        self.max - self.min
+    }
+
+    /// surface area, the cost measure of bounding volume trees
+    pub fn surface_area(&self) -> S {
+        let e = self.extent();
+        S::from_f32(2.0) * (e.x * e.y + e.y * e.z + e.z * e.x)
+    }
+
+    /// Fraction of `dir_` at which the ray from `origin_` enters the box, zero when it starts inside,
+    /// or `None` when it misses the box before `max_fraction_`.
+    pub fn ray_cast(&self, origin_:&Float3<S>, dir_:&Float3<S>, max_fraction_:S) -> Option<S> {
+        let mut enter = S::ZERO;
+        let mut exit = max_fraction_;
+        for k in 0..3 {
+            let (o, d) = (axis(origin_, k), axis(dir_, k));
+            let (lo, hi) = (axis(&self.min, k), axis(&self.max, k));
+            if d == S::ZERO {
+                if o < lo || o > hi { return None }
+                continue
+            }
+            let (t0, t1) = ((lo - o) / d, (hi - o) / d);
+            enter = enter.max(t0.min(t1));
+            exit = exit.min(t0.max(t1));
+            if enter > exit { return None }
+        }
+        Some(enter)
+    }
+}
+
+/// component `i_` of `p_`, x for 0, y for 1 and z otherwise
+pub(crate) fn axis<S>(p_:&Float3<S>, i_:usize) -> S where S: Scalar {
+    match i_ {
+        0 => p_.x,
+        1 => p_.y,
+        _ => p_.z,
+    }
+}
+
+fn set_axis<S>(p_:&mut Float3<S>, i_:usize, v_:S) where S: Scalar {
+    match i_ {
+        0 => p_.x = v_,
+        1 => p_.y = v_,
+        _ => p_.z = v_,
+    }
+}
+
+// endregion
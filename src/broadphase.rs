@@ -0,0 +1,684 @@
+use std::collections::HashMap;
+use crate::vector::{Float3, Scalar};
+use crate::aabb::{axis, Aabb};
+use crate::simplex_based_cd_helper::Error;
+
+// region Broadphase
+
+/// Culls the object pairs whose bounding boxes are apart, leaving candidates for the narrowphase.
+pub trait Broadphase<S = f32> {
+    /// Replaces the content of `pair_` with every pair `(i, j)`, `i < j`, of indices into `aabb_` whose boxes overlap,
+    /// in ascending order. Empty boxes overlap nothing.
+    fn pairs(&mut self, aabb_:&[Aabb<S>], pair_:&mut Vec<(usize, usize)>);
+}
+
+// endregion
+
+// region SweepAndPrune
+
+/// Sorts the boxes by their lower bound along one axis and sweeps over them,
+/// testing each box only against the boxes whose span on that axis it starts in.
+///
+/// The order is kept between calls, so sorting boxes that moved little takes close to linear time.
+#[derive(Debug, Clone, Default)]
+pub struct SweepAndPrune {
+    /// box indices by lower bound along `axis`
+    order: Vec<usize>,
+    axis: usize,
+    /// boxes whose span contains the current sweep position
+    active: Vec<usize>,
+}
+
+impl SweepAndPrune {
+    pub fn new() -> SweepAndPrune {
+        SweepAndPrune::default()
+    }
+}
+
+impl<S> Broadphase<S> for SweepAndPrune where S: Scalar {
+    fn pairs(&mut self, aabb_:&[Aabb<S>], pair_:&mut Vec<(usize, usize)>) {
+        pair_.clear();
+        //sweep along the axis the centers spread the most on, it leaves the fewest boxes overlapping on it alone
+        let mut sum = Float3::zero();
+        let mut sqr_sum = Float3::zero();
+        let mut count = S::ZERO;
+        for b in aabb_.iter().filter(|b| !b.is_empty()) {
+            let c = b.center();
+            sum = sum + c;
+            sqr_sum = sqr_sum + Float3 {x:c.x * c.x, y:c.y * c.y, z:c.z * c.z};
+            count = count + S::ONE;
+        }
+        if count > S::ZERO {
+            let spread = |k| axis(&sqr_sum, k) - axis(&sum, k) * axis(&sum, k) / count;
+            self.axis = (0..3).fold(0, |best, k| if spread(k) > spread(best) { k } else { best });
+        }
+        let k = self.axis;
+        if self.order.len() != aabb_.len() { self.order = (0..aabb_.len()).collect(); }
+        self.order.sort_by(|a, b| axis(&aabb_[*a].min, k).partial_cmp(&axis(&aabb_[*b].min, k)).unwrap_or(std::cmp::Ordering::Equal));
+        self.active.clear();
+        for &i in &self.order {
+            let b = &aabb_[i];
+            if b.is_empty() { continue }
+            let lo = axis(&b.min, k);
+            self.active.retain(|j| axis(&aabb_[*j].max, k) >= lo);
+            for &j in &self.active {
+                if b.overlaps(&aabb_[j]) { pair_.push((i.min(j), i.max(j))); }
+            }
+            self.active.push(i);
+        }
+        pair_.sort_unstable();
+    }
+}
+
+// endregion
+
+// region DynamicTree
+
+/// Identifies a box inserted into a `DynamicTree`, stable until it is removed.
+pub type ProxyId = usize;
+
+const NULL_NODE: usize = usize::MAX;
+const FREE_NODE: usize = usize::MAX;
+
+#[derive(Debug, Clone)]
+struct TreeNode<S> {
+    /// fattened box for leaves, union of the children otherwise
+    aabb: Aabb<S>,
+    parent: usize,
+    /// `NULL_NODE` for leaves
+    child: [usize; 2],
+    /// zero for leaves, `FREE_NODE` once freed
+    height: usize,
+    /// user value of leaves
+    data: usize,
+}
+
+impl<S> TreeNode<S> {
+    fn is_leaf(&self) -> bool { self.child[0] == NULL_NODE }
+
+    fn is_proxy(&self) -> bool { self.is_leaf() && self.height != FREE_NODE }
+}
+
+/// Bounding volume hierarchy over fattened boxes, balanced by rotations and updated incrementally.
+///
+/// Each leaf stores its box grown by `margin`, so a moving object is only reinserted
+/// once it leaves its fat box. Suits scenes with clustered or fast moving objects,
+/// where the sort order of `SweepAndPrune` changes too much between frames.
+#[derive(Debug, Clone)]
+pub struct DynamicTree<S = f32> {
+    /// distance the leaf boxes are grown by on every side, zero or more and finite
+    pub margin: S,
+    node: Vec<TreeNode<S>>,
+    free: Vec<usize>,
+    root: usize,
+    /// proxies of the boxes passed to `Broadphase::pairs`, by index
+    object: Vec<Option<ProxyId>>,
+    /// proxies inserted, reinserted or removed since the last `overlapping_pairs`
+    moved: Vec<ProxyId>,
+    /// fat box pairs found by the last `overlapping_pairs`
+    pair: Vec<(ProxyId, ProxyId)>,
+    stack: Vec<usize>,
+}
+
+impl<S> DynamicTree<S> where S: Scalar {
+    /// Fails with `Error::InvalidInput` unless `margin_` is zero or more and finite:
+    /// a negative margin shrinks the leaf boxes and loses pairs.
+    pub fn new(margin_:S) -> Result<DynamicTree<S>, Error> {
+        if !is_margin(margin_) { return Err(Error::InvalidInput) }
+        Ok(DynamicTree {margin:margin_, node:Vec::new(), free:Vec::new(), root:NULL_NODE, object:Vec::new(), moved:Vec::new(), pair:Vec::new(), stack:Vec::new()})
+    }
+
+    /// Adds `aabb_`, fattened by `margin`, carrying `data_` for the caller to map proxies back to objects.
+    pub fn insert(&mut self, aabb_:&Aabb<S>, data_:usize) -> ProxyId {
+        let leaf = self.allocate(aabb_.expanded(self.margin), data_);
+        self.insert_leaf(leaf);
+        self.moved.push(leaf);
+        leaf
+    }
+
+    pub fn remove(&mut self, proxy_:ProxyId) {
+        self.remove_leaf(proxy_);
+        self.release(proxy_);
+        self.moved.push(proxy_);
+    }
+
+    /// Updates the box of `proxy_` once `aabb_` leaves its fat box, stretching the new fat box along `displacement_`,
+    /// the expected motion until the next update. Returns whether the proxy was reinserted.
+    pub fn move_proxy(&mut self, proxy_:ProxyId, aabb_:&Aabb<S>, displacement_:&Float3<S>) -> bool {
+        if self.node[proxy_].aabb.contains(aabb_) { return false }
+        self.remove_leaf(proxy_);
+        let mut fat = aabb_.expanded(self.margin);
+        let d = displacement_;
+        if d.x < S::ZERO { fat.min.x = fat.min.x + d.x } else { fat.max.x = fat.max.x + d.x }
+        if d.y < S::ZERO { fat.min.y = fat.min.y + d.y } else { fat.max.y = fat.max.y + d.y }
+        if d.z < S::ZERO { fat.min.z = fat.min.z + d.z } else { fat.max.z = fat.max.z + d.z }
+        self.node[proxy_].aabb = fat;
+        self.insert_leaf(proxy_);
+        self.moved.push(proxy_);
+        true
+    }
+
+    pub fn fat_aabb(&self, proxy_:ProxyId) -> &Aabb<S> { &self.node[proxy_].aabb }
+
+    pub fn data(&self, proxy_:ProxyId) -> usize { self.node[proxy_].data }
+
+    /// height of the tree, zero when it holds at most one box
+    pub fn height(&self) -> usize {
+        if self.root == NULL_NODE { 0 } else { self.node[self.root].height }
+    }
+
+    /// Replaces the content of `out_` with the proxies whose fat box overlaps `aabb_`.
+    pub fn query(&mut self, aabb_:&Aabb<S>, out_:&mut Vec<ProxyId>) {
+        out_.clear();
+        self.stack.clear();
+        if self.root != NULL_NODE { self.stack.push(self.root); }
+        while let Some(i) = self.stack.pop() {
+            let n = &self.node[i];
+            if !n.aabb.overlaps(aabb_) { continue }
+            if n.is_leaf() { out_.push(i); } else { self.stack.extend_from_slice(&n.child); }
+        }
+    }
+
+    /// Replaces the content of `out_` with the proxies whose fat box the ray `origin_ + dir_ * t`, `t` in `[0, max_fraction_]`,
+    /// passes through, with the fraction it enters them at, nearest first.
+    /// The shapes themselves can then be tested in order with `gjk3d::ray_cast`.
+    pub fn ray_cast(&mut self, origin_:&Float3<S>, dir_:&Float3<S>, max_fraction_:S, out_:&mut Vec<(ProxyId, S)>) {
+        out_.clear();
+        self.stack.clear();
+        if self.root != NULL_NODE { self.stack.push(self.root); }
+        while let Some(i) = self.stack.pop() {
+            let n = &self.node[i];
+            let Some(t) = n.aabb.ray_cast(origin_, dir_, max_fraction_) else { continue };
+            if n.is_leaf() { out_.push((i, t)); } else { self.stack.extend_from_slice(&n.child); }
+        }
+        out_.sort_by(|a, b| a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal));
+    }
+
+    /// Replaces the content of `out_` with every pair of proxies whose fat boxes overlap, smaller id first, in ascending order.
+    /// Only the proxies that moved since the last call are queried, the pairs of the others still hold.
+    pub fn overlapping_pairs(&mut self, out_:&mut Vec<(ProxyId, ProxyId)>) {
+        let mut moved = std::mem::take(&mut self.moved);
+        moved.sort_unstable();
+        moved.dedup();
+        self.pair.retain(|(p, q)| moved.binary_search(p).is_err() && moved.binary_search(q).is_err());
+        let mut hit = Vec::new();
+        for &p in &moved {
+            //removed proxies may have been freed or reused for an inner node since
+            if !self.node[p].is_proxy() { continue }
+            let aabb = self.node[p].aabb;
+            self.query(&aabb, &mut hit);
+            self.pair.extend(hit.iter().filter(|q| **q != p).map(|q| (p.min(*q), p.max(*q))));
+        }
+        self.pair.sort_unstable();
+        self.pair.dedup();
+        out_.clear();
+        out_.extend_from_slice(&self.pair);
+        moved.clear();
+        self.moved = moved;
+    }
+
+    fn release(&mut self, i_:usize) {
+        let n = &mut self.node[i_];
+        n.parent = NULL_NODE;
+        n.child = [NULL_NODE; 2];
+        n.height = FREE_NODE;
+        self.free.push(i_);
+    }
+
+    fn allocate(&mut self, aabb_:Aabb<S>, data_:usize) -> usize {
+        let n = TreeNode {aabb:aabb_, parent:NULL_NODE, child:[NULL_NODE; 2], height:0, data:data_};
+        match self.free.pop() {
+            Some(i) => {
+                self.node[i] = n;
+                i
+            },
+            None => {
+                self.node.push(n);
+                self.node.len() - 1
+            },
+        }
+    }
+
+    /// Attaches `leaf_` next to the node that grows the total surface area the least.
+    fn insert_leaf(&mut self, leaf_:usize) {
+        if self.root == NULL_NODE {
+            self.root = leaf_;
+            self.node[leaf_].parent = NULL_NODE;
+            return
+        }
+        let leaf_box = self.node[leaf_].aabb;
+        let two = S::from_f32(2.0);
+        let mut i = self.root;
+        while !self.node[i].is_leaf() {
+            let area = self.node[i].aabb.surface_area();
+            let combined = self.node[i].aabb.merged(&leaf_box).surface_area();
+            //pairing with `i` itself, versus the growth every ancestor of a child takes on
+            let cost = two * combined;
+            let inherited = two * (combined - area);
+            let child_cost = |c:usize| {
+                let n = &self.node[c];
+                let merged = n.aabb.merged(&leaf_box).surface_area();
+                if n.is_leaf() { merged + inherited } else { merged - n.aabb.surface_area() + inherited }
+            };
+            let [c0, c1] = self.node[i].child;
+            let (cost0, cost1) = (child_cost(c0), child_cost(c1));
+            if cost < cost0 && cost < cost1 { break }
+            i = if cost0 < cost1 { c0 } else { c1 };
+        }
+        let sibling = i;
+        let old_parent = self.node[sibling].parent;
+        let parent = self.allocate(leaf_box.merged(&self.node[sibling].aabb), 0);
+        self.node[parent].parent = old_parent;
+        self.node[parent].child = [sibling, leaf_];
+        self.node[parent].height = self.node[sibling].height + 1;
+        if old_parent == NULL_NODE { self.root = parent; } else { self.replace_child(old_parent, sibling, parent); }
+        self.node[sibling].parent = parent;
+        self.node[leaf_].parent = parent;
+        self.refit(parent);
+    }
+
+    fn remove_leaf(&mut self, leaf_:usize) {
+        if leaf_ == self.root {
+            self.root = NULL_NODE;
+            return
+        }
+        let parent = self.node[leaf_].parent;
+        let grand = self.node[parent].parent;
+        let sibling = if self.node[parent].child[0] == leaf_ { self.node[parent].child[1] } else { self.node[parent].child[0] };
+        self.node[leaf_].parent = NULL_NODE;
+        self.release(parent);
+        self.node[sibling].parent = grand;
+        if grand == NULL_NODE {
+            self.root = sibling;
+        } else {
+            self.replace_child(grand, parent, sibling);
+            self.refit(grand);
+        }
+    }
+
+    fn replace_child(&mut self, parent_:usize, old_:usize, new_:usize) {
+        let child = &mut self.node[parent_].child;
+        if child[0] == old_ { child[0] = new_ } else { child[1] = new_ }
+    }
+
+    /// Rebalances and recomputes the boxes and heights from `i_` up to the root.
+    fn refit(&mut self, mut i_:usize) {
+        while i_ != NULL_NODE {
+            i_ = self.balance(i_);
+            self.update_node(i_);
+            i_ = self.node[i_].parent;
+        }
+    }
+
+    fn update_node(&mut self, i_:usize) {
+        let [c0, c1] = self.node[i_].child;
+        self.node[i_].aabb = self.node[c0].aabb.merged(&self.node[c1].aabb);
+        self.node[i_].height = 1 + self.node[c0].height.max(self.node[c1].height);
+    }
+
+    /// Rotates the taller child of `a_` above it when the heights of its children differ by more than one,
+    /// returning the node now at the place of `a_`.
+    fn balance(&mut self, a_:usize) -> usize {
+        let a = &self.node[a_];
+        if a.is_leaf() || a.height < 2 { return a_ }
+        let [b, c] = a.child;
+        let (hb, hc) = (self.node[b].height, self.node[c].height);
+        if hb.abs_diff(hc) < 2 { return a_ }
+        let (up, side) = if hc > hb { (c, 1) } else { (b, 0) };
+        //`up` takes the place of `a_`, keeping its taller child, and hands the shorter one to `a_`
+        let [f, g] = self.node[up].child;
+        let (tall, short) = if self.node[f].height >= self.node[g].height { (f, g) } else { (g, f) };
+        let parent = self.node[a_].parent;
+        self.node[up].parent = parent;
+        if parent == NULL_NODE { self.root = up; } else { self.replace_child(parent, a_, up); }
+        self.node[up].child = [a_, tall];
+        self.node[a_].parent = up;
+        self.node[a_].child[side] = short;
+        self.node[short].parent = a_;
+        self.update_node(a_);
+        self.update_node(up);
+        up
+    }
+}
+
+impl<S> Default for DynamicTree<S> where S: Scalar {
+    fn default() -> Self {
+        DynamicTree {margin:S::from_f32(0.1), node:Vec::new(), free:Vec::new(), root:NULL_NODE, object:Vec::new(), moved:Vec::new(), pair:Vec::new(), stack:Vec::new()}
+    }
+}
+
+/// whether `v_` can grow leaf boxes
+fn is_margin<S>(v_:S) -> bool where S: Scalar {
+    v_.to_f64().is_finite() && v_ >= S::ZERO
+}
+
+impl<S> Broadphase<S> for DynamicTree<S> where S: Scalar {
+    /// Keeps one proxy per box index, moved to the new boxes on each call, and reports the pairs whose own boxes overlap.
+    fn pairs(&mut self, aabb_:&[Aabb<S>], pair_:&mut Vec<(usize, usize)>) {
+        while self.object.len() > aabb_.len() {
+            if let Some(p) = self.object.pop().flatten() { self.remove(p); }
+        }
+        self.object.resize(aabb_.len(), None);
+        for (i, b) in aabb_.iter().enumerate() {
+            match (self.object[i], b.is_empty()) {
+                (Some(p), true) => {
+                    self.remove(p);
+                    self.object[i] = None;
+                },
+                (Some(p), false) => { self.move_proxy(p, b, &Float3::zero()); },
+                (None, false) => self.object[i] = Some(self.insert(b, i)),
+                (None, true) => {},
+            }
+        }
+        let mut proxy_pair = Vec::new();
+        self.overlapping_pairs(&mut proxy_pair);
+        pair_.clear();
+        for (p, q) in proxy_pair {
+            let (i, j) = (self.node[p].data, self.node[q].data);
+            if aabb_[i].overlaps(&aabb_[j]) { pair_.push((i.min(j), i.max(j))); }
+        }
+        pair_.sort_unstable();
+    }
+}
+
+// endregion
+
+// region SpatialHash
+
+/// boxes covering more cells are tested against every box instead of being hashed
+const MAX_CELL_PER_BOX: i64 = 64;
+
+/// Uniform grid of cubic cells, hashed by cell coordinates, each listing the boxes overlapping it.
+///
+/// Suits many objects of similar size, with `cell_size` about their diameter:
+/// each box then covers a few cells and is tested against the boxes sharing them only.
+#[derive(Debug, Clone)]
+pub struct SpatialHash<S = f32> {
+    /// positive and finite
+    pub cell_size: S,
+    cell: HashMap<(i64, i64, i64), Vec<usize>>,
+    /// boxes spanning more than `MAX_CELL_PER_BOX` cells
+    large: Vec<usize>,
+}
+
+impl<S> SpatialHash<S> where S: Scalar {
+    /// Fails with `Error::InvalidInput` unless `cell_size_` is positive and finite.
+    pub fn new(cell_size_:S) -> Result<SpatialHash<S>, Error> {
+        if !is_positive_size(cell_size_) { return Err(Error::InvalidInput) }
+        Ok(SpatialHash {cell_size:cell_size_, cell:HashMap::new(), large:Vec::new()})
+    }
+
+    /// coordinates of the cell containing `p_`
+    fn cell_of(&self, p_:&Float3<S>) -> (i64, i64, i64) {
+        let size = self.cell_size.to_f64();
+        let c = |v:S| (v.to_f64() / size).floor() as i64;
+        (c(p_.x), c(p_.y), c(p_.z))
+    }
+}
+
+impl<S> Default for SpatialHash<S> where S: Scalar {
+    fn default() -> Self { SpatialHash {cell_size:S::ONE, cell:HashMap::new(), large:Vec::new()} }
+}
+
+impl<S> Broadphase<S> for SpatialHash<S> where S: Scalar {
+    fn pairs(&mut self, aabb_:&[Aabb<S>], pair_:&mut Vec<(usize, usize)>) {
+        pair_.clear();
+        //keep the cells used last time along with their capacity, coherent scenes mostly reuse them
+        self.cell.retain(|_, list| !list.is_empty());
+        for list in self.cell.values_mut() {
+            list.clear();
+        }
+        self.large.clear();
+        for (i, b) in aabb_.iter().enumerate() {
+            if b.is_empty() { continue }
+            let (lo, hi) = (self.cell_of(&b.min), self.cell_of(&b.max));
+            //saturating, as far or huge boxes give cell coordinates at the limits of i64
+            let span = |a:i64, b:i64| b.saturating_sub(a).saturating_add(1);
+            if span(lo.0, hi.0).saturating_mul(span(lo.1, hi.1)).saturating_mul(span(lo.2, hi.2)) > MAX_CELL_PER_BOX {
+                self.large.push(i);
+                continue
+            }
+            for x in lo.0..=hi.0 {
+                for y in lo.1..=hi.1 {
+                    for z in lo.2..=hi.2 {
+                        self.cell.entry((x, y, z)).or_default().push(i);
+                    }
+                }
+            }
+        }
+        for (key, list) in &self.cell {
+            for (k, &i) in list.iter().enumerate() {
+                for &j in &list[k + 1..] {
+                    let (a, b) = (&aabb_[i], &aabb_[j]);
+                    if !a.overlaps(b) { continue }
+                    //boxes sharing several cells are reported by the one holding the low corner of their overlap
+                    let corner = Float3 {x:a.min.x.max(b.min.x), y:a.min.y.max(b.min.y), z:a.min.z.max(b.min.z)};
+                    if self.cell_of(&corner) == *key { pair_.push((i.min(j), i.max(j))); }
+                }
+            }
+        }
+        for (k, &i) in self.large.iter().enumerate() {
+            for (j, b) in aabb_.iter().enumerate() {
+                //pairs of large boxes come up twice, keep the one found from the first
+                if j == i || b.is_empty() || self.large[..k].contains(&j) { continue }
+                if aabb_[i].overlaps(b) { pair_.push((i.min(j), i.max(j))); }
+            }
+        }
+        pair_.sort_unstable();
+    }
+}
+
+// endregion
+
+// region BroadphaseKind
+
+/// Broadphase chosen at run time, for instance to compare them on the same scene.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BroadphaseKind<S = f32> {
+    SweepAndPrune,
+    DynamicTree {margin: S},
+    SpatialHash {cell_size: S},
+}
+
+impl<S> BroadphaseKind<S> where S: Scalar + 'static {
+    /// Fails with `Error::InvalidInput` when the margin of a `DynamicTree` is negative or not finite,
+    /// or the cell size of a `SpatialHash` is not positive and finite.
+    pub fn create(&self) -> Result<Box<dyn Broadphase<S>>, Error> {
+        Ok(match *self {
+            BroadphaseKind::SweepAndPrune => Box::new(SweepAndPrune::new()),
+            BroadphaseKind::DynamicTree {margin} => Box::new(DynamicTree::new(margin)?),
+            BroadphaseKind::SpatialHash {cell_size} => Box::new(SpatialHash::new(cell_size)?),
+        })
+    }
+}
+
+/// whether `v_` can size grid cells
+fn is_positive_size<S>(v_:S) -> bool where S: Scalar {
+    v_.to_f64().is_finite() && v_ > S::ZERO
+}
+
+impl<S> std::str::FromStr for BroadphaseKind<S> where S: Scalar {
+    type Err = String;
+
+    /// `sap`, `tree[:margin]` or `grid[:cell_size]`, the size defaulting to 0.1 and 1 respectively
+    fn from_str(s_:&str) -> Result<Self, Self::Err> {
+        let (name, size) = match s_.split_once(':') {
+            Some((n, v)) => (n, Some(v.parse::<f32>().map_err(|e| format!("invalid broadphase size {}: {}", v, e))?)),
+            None => (s_, None),
+        };
+        match name {
+            "sap" => Ok(BroadphaseKind::SweepAndPrune),
+            "tree" => {
+                let margin = S::from_f32(size.unwrap_or(0.1));
+                if !is_margin(margin) { return Err(format!("invalid tree margin {}, it must be zero or more and finite", margin.to_f64())) }
+                Ok(BroadphaseKind::DynamicTree {margin})
+            },
+            "grid" => {
+                let cell_size = S::from_f32(size.unwrap_or(1.0));
+                if !is_positive_size(cell_size) { return Err(format!("invalid grid cell size {}, it must be positive and finite", cell_size.to_f64())) }
+                Ok(BroadphaseKind::SpatialHash {cell_size})
+            },
+            _ => Err(format!("unknown broadphase {}, expected sap, tree or grid", s_)),
+        }
+    }
+}
+
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// deterministic numbers in [0, 1)
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> f32 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 40) as f32 / (1u64 << 24) as f32
+        }
+
+        fn point(&mut self, scale_:f32) -> Float3 {
+            Float3 {x:self.next() * scale_, y:self.next() * scale_, z:self.next() * scale_}
+        }
+    }
+
+    /// Boxes of a few sizes moving over several frames, some empty, some sharing a face, and the count changing.
+    fn frames() -> Vec<Vec<Aabb>> {
+        let mut rng = Lcg(11);
+        let mut center: Vec<Float3> = (0..120).map(|_| rng.point(10.0)).collect();
+        let half: Vec<Float3> = (0..120).map(|i| rng.point(if i % 20 == 0 { 4.0 } else { 0.6 })).collect();
+        let velocity: Vec<Float3> = (0..120).map(|_| rng.point(0.4) - Float3 {x:0.2, y:0.2, z:0.2}).collect();
+        let mut ret = Vec::new();
+        for frame in 0..30 {
+            let count = if frame % 10 < 5 { 120 } else { 90 + frame % 7 };
+            let mut aabb: Vec<Aabb> = (0..count).map(|i| Aabb::new(center[i] - half[i], center[i] + half[i])).collect();
+            aabb[3] = Aabb::empty();
+            //face to face with box 0
+            aabb[4] = Aabb::new(Float3 {x:aabb[0].max.x, y:aabb[0].min.y, z:aabb[0].min.z}, aabb[0].max + Float3 {x:1.0, y:0.0, z:0.0});
+            ret.push(aabb);
+            for (c, v) in center.iter_mut().zip(&velocity) {
+                *c = *c + *v;
+            }
+        }
+        ret
+    }
+
+    fn brute_force(aabb_:&[Aabb]) -> Vec<(usize, usize)> {
+        let mut ret = Vec::new();
+        for i in 0..aabb_.len() {
+            for j in i + 1..aabb_.len() {
+                if !aabb_[i].is_empty() && !aabb_[j].is_empty() && aabb_[i].overlaps(&aabb_[j]) { ret.push((i, j)); }
+            }
+        }
+        ret
+    }
+
+    fn check_against_brute_force(broadphase_:&mut dyn Broadphase) {
+        let mut pair = Vec::new();
+        for aabb in frames() {
+            broadphase_.pairs(&aabb, &mut pair);
+            assert_eq!(pair, brute_force(&aabb));
+        }
+    }
+
+    #[test]
+    fn sweep_and_prune_pairs() {
+        check_against_brute_force(&mut SweepAndPrune::new());
+    }
+
+    #[test]
+    fn dynamic_tree_pairs() {
+        //no margin reinserts every moving box, a wide one keeps most fat boxes and their old pairs
+        for margin in [0.0, 0.1, 2.0] {
+            check_against_brute_force(&mut DynamicTree::new(margin).unwrap());
+        }
+    }
+
+    /// tree holding the boxes of the first frame, moved through the following ones
+    fn moved_tree() -> (DynamicTree, Vec<ProxyId>) {
+        let frame = frames();
+        let mut tree = DynamicTree::new(0.1).unwrap();
+        let proxy: Vec<ProxyId> = frame[0].iter().enumerate().filter(|(_, b)| !b.is_empty()).map(|(i, b)| tree.insert(b, i)).collect();
+        for aabb in &frame[1..5] {
+            for &p in &proxy {
+                let i = tree.data(p);
+                tree.move_proxy(p, &aabb[i], &Float3 {x:0.1, y:0.0, z:0.0});
+            }
+        }
+        (tree, proxy)
+    }
+
+    #[test]
+    fn dynamic_tree_query() {
+        let (mut tree, proxy) = moved_tree();
+        let mut rng = Lcg(13);
+        let mut hit = Vec::new();
+        for _ in 0..200 {
+            let lo = rng.point(14.0) - Float3 {x:2.0, y:2.0, z:2.0};
+            let aabb = Aabb::new(lo, lo + rng.point(3.0));
+            tree.query(&aabb, &mut hit);
+            hit.sort_unstable();
+            let expected: Vec<ProxyId> = proxy.iter().copied().filter(|p| tree.fat_aabb(*p).overlaps(&aabb)).collect();
+            assert_eq!(hit, expected);
+        }
+    }
+
+    #[test]
+    fn dynamic_tree_ray_cast() {
+        let (mut tree, proxy) = moved_tree();
+        let mut rng = Lcg(19);
+        let mut hit = Vec::new();
+        for k in 0..200 {
+            let origin = rng.point(14.0) - Float3 {x:2.0, y:2.0, z:2.0};
+            //some rays along an axis, their other components exactly zero
+            let dir = if k % 4 == 0 { Float3 {x:12.0, y:0.0, z:0.0} } else { rng.point(24.0) - Float3 {x:12.0, y:12.0, z:12.0} };
+            tree.ray_cast(&origin, &dir, 1.0, &mut hit);
+            assert!(hit.windows(2).all(|w| w[0].1 <= w[1].1));
+            let mut found: Vec<(ProxyId, f32)> = hit.clone();
+            found.sort_by_key(|h| h.0);
+            let expected: Vec<(ProxyId, f32)> = proxy.iter().filter_map(|p| tree.fat_aabb(*p).ray_cast(&origin, &dir, 1.0).map(|t| (*p, t))).collect();
+            assert_eq!(found, expected);
+        }
+    }
+
+    #[test]
+    fn invalid_margin() {
+        for margin in [-1.0, f32::NAN, f32::INFINITY] {
+            assert!(DynamicTree::new(margin).is_err());
+        }
+        assert!(DynamicTree::new(0.0).is_ok());
+    }
+
+    #[test]
+    fn spatial_hash_pairs() {
+        //cells smaller and larger than the boxes, the small ones sending the largest boxes to the brute force list
+        for cell_size in [0.3, 1.0, 5.0] {
+            check_against_brute_force(&mut SpatialHash::new(cell_size).unwrap());
+        }
+    }
+
+    #[test]
+    fn invalid_tree_margin() {
+        for margin in [-5.0, f32::NAN, f32::INFINITY] {
+            assert!(BroadphaseKind::DynamicTree {margin}.create().is_err());
+        }
+        for s in ["tree:-5", "tree:nan", "tree:inf", "tree:1e40"] {
+            assert!(s.parse::<BroadphaseKind>().is_err(), "{}", s);
+        }
+        assert_eq!("tree:0".parse::<BroadphaseKind>(), Ok(BroadphaseKind::DynamicTree {margin:0.0}));
+        assert_eq!("tree".parse::<BroadphaseKind>(), Ok(BroadphaseKind::DynamicTree {margin:0.1}));
+    }
+
+    #[test]
+    fn invalid_cell_size() {
+        for cell_size in [0.0, -1.0, f32::NAN, f32::INFINITY] {
+            assert!(SpatialHash::new(cell_size).is_err());
+            assert!(BroadphaseKind::SpatialHash {cell_size}.create().is_err());
+        }
+        for s in ["grid:0", "grid:-2", "grid:nan", "grid:inf", "grid:1e40"] {
+            assert!(s.parse::<BroadphaseKind>().is_err());
+        }
+        assert_eq!("grid:0.5".parse::<BroadphaseKind>(), Ok(BroadphaseKind::SpatialHash {cell_size:0.5}));
+    }
+}
@@ -0,0 +1,138 @@
+use crate::vector::{Float3, Isometry, Quat, Scalar};
+use crate::simplex_based_cd_helper::{Error, Frame3Simplex, ShapeCastHit};
+use crate::support_map::SupportMap;
+use crate::shape::Posed;
+use crate::gjk3d;
+
+// region Motion
+
+/// Rigid motion over a time step: `pose` at its start, then constant linear and angular velocity.
+/// The angular velocity is in world space and turns the shape around the origin of its local space.
+#[derive(Debug, Clone, Copy)]
+pub struct Motion<S = f32> {
+    pub pose: Isometry<S>,
+    pub linear: Float3<S>,
+    pub angular: Float3<S>,
+}
+
+impl<S> Motion<S> where S: Scalar {
+    pub fn new(pose_:Isometry<S>, linear_:Float3<S>, angular_:Float3<S>) -> Motion<S> {
+        Motion {pose:pose_, linear:linear_, angular:angular_}
+    }
+
+    /// pose reached after `t_`
+    pub fn pose_at(&self, t_:S) -> Isometry<S> {
+        let mut rotation = Quat::from_scaled_axis(&(self.angular * t_)) * self.pose.rotation;
+        rotation.normalize();
+        Isometry::new(self.pose.translation + self.linear * t_, rotation)
+    }
+}
+
+// endregion
+
+// region ConservativeAdvancement
+
+/// Time of impact of rotating and translating shapes by conservative advancement:
+/// the shapes are moved by the largest step their current distance and velocity bounds allow,
+/// until they are closer than `tolerance`.
+#[derive(Debug, Clone, Copy)]
+pub struct ConservativeAdvancement<S = f32> {
+    /// distance at which the shapes count as touching
+    pub tolerance: S,
+    pub max_iteration: usize,
+}
+
+impl<S> ConservativeAdvancement<S> where S: Scalar {
+    pub fn new(tolerance_:S, max_iteration_:usize) -> ConservativeAdvancement<S> {
+        ConservativeAdvancement {tolerance:tolerance_, max_iteration:max_iteration_}
+    }
+
+    /// First time in `[0, dt_]` the local shapes `polya_` and `polyb_` come within `tolerance` of each other
+    /// while following `motion_a_` and `motion_b_`.
+    /// Shapes overlapping from the start hit at time zero with a zero normal.
+    pub fn time_of_impact<A, B>(
+        &self, polya_:&A, motion_a_:&Motion<S>, polyb_:&B, motion_b_:&Motion<S>, dt_:S, f_:&mut Frame3Simplex<Float3<S>>
+    ) -> Result<Option<ShapeCastHit<Float3<S>>>, Error>
+        where A: SupportMap<Float3<S>> + ?Sized, B: SupportMap<Float3<S>> + ?Sized
+    {
+        if !polya_.is_valid() || !polyb_.is_valid() { return Err(Error::InvalidInput) }
+        //no point of a shape moves faster than its linear velocity plus its angular speed times its radius
+        let spin = motion_a_.angular.magnitude() * bounding_radius(polya_) + motion_b_.angular.magnitude() * bounding_radius(polyb_);
+        let mut t = S::ZERO;
+        let mut normal = Float3::zero();
+        let mut iteration = 0;
+        loop {
+            let a = Posed::new(polya_, motion_a_.pose_at(t));
+            let b = Posed::new(polyb_, motion_b_.pose_at(t));
+            let d = gjk3d::distance(&a, &b, f_)?;
+            //an exact step leaves the shapes touching, without a normal of their own
+            if !d.normal.is_zero() { normal = d.normal; }
+            if d.distance <= self.tolerance {
+                break Ok(Some(ShapeCastHit {toi:t, normal, closest_a:d.closest_a, closest_b:d.closest_b}))
+            }
+            let bound = d.normal.dot(&(motion_a_.linear - motion_b_.linear)) + spin;
+            if bound <= S::ZERO { break Ok(None) }
+            t = t + d.distance / bound;
+            if t > dt_ { break Ok(None) }
+            iteration += 1;
+            if iteration >= self.max_iteration { break Err(Error::TimeOfImpact) }
+        }
+    }
+}
+
+impl<S> Default for ConservativeAdvancement<S> where S: Scalar {
+    fn default() -> Self { ConservativeAdvancement::new(S::from_f32(1e-3), 64) }
+}
+
+/// Distance from the local origin of `poly_` to the corner of its bounding box, which bounds every point of it.
+fn bounding_radius<S, A>(poly_:&A) -> S where S: Scalar, A: SupportMap<Float3<S>> + ?Sized {
+    let (zero, one) = (S::ZERO, S::ONE);
+    let mut corner = Float3::zero();
+    for d in [Float3 {x:one, y:zero, z:zero}, Float3 {x:zero, y:one, z:zero}, Float3 {x:zero, y:zero, z:one}] {
+        let hi = poly_.support(&d).0.dot(&d);
+        let lo = poly_.support(&-d).0.dot(&d);
+        corner = corner + d * hi.abs().max(lo.abs());
+    }
+    corner.magnitude()
+}
+
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::{OrientedBox, Sphere};
+
+    fn at(x_:f32, y_:f32) -> Isometry {
+        Isometry::new(Float3 {x:x_, y:y_, z:0.0}, Quat::identity())
+    }
+
+    #[test]
+    fn spheres_closing_in() {
+        //4 apart closing at 2, the unit spheres touch at t = 1
+        let sphere = Sphere {center:Float3::zero(), radius:1.0};
+        let motion_a = Motion::new(at(0.0, 0.0), Float3 {x:1.0, y:0.0, z:0.0}, Float3::zero());
+        let motion_b = Motion::new(at(4.0, 0.0), Float3 {x:-1.0, y:0.0, z:0.0}, Float3::zero());
+        let ca = ConservativeAdvancement::default();
+        let hit = ca.time_of_impact(&sphere, &motion_a, &sphere, &motion_b, 2.0, &mut Frame3Simplex::new()).unwrap().unwrap();
+        assert!(hit.toi <= 1.0 && hit.toi >= 1.0 - ca.tolerance);
+        assert!((hit.normal - Float3 {x:1.0, y:0.0, z:0.0}).magnitude() <= 1e-3);
+        assert!(ca.time_of_impact(&sphere, &motion_a, &sphere, &motion_b, 0.9, &mut Frame3Simplex::new()).unwrap().is_none());
+    }
+
+    #[test]
+    fn spinning_rod_reaches_sphere() {
+        //a rod along x turning around z at 1 rad per unit time, the face towards the sphere at `(0, 1.5)` is
+        //`1.5 * cos(t) - 0.05` from its center, which falls to the radius 0.5 at `cos(t) = 0.55 / 1.5`
+        let rod = OrientedBox::axis_aligned(Float3::zero(), Float3 {x:2.0, y:0.05, z:0.05});
+        let sphere = Sphere {center:Float3::zero(), radius:0.5};
+        let motion_a = Motion::new(at(0.0, 0.0), Float3::zero(), Float3 {x:0.0, y:0.0, z:1.0});
+        let motion_b = Motion::new(at(0.0, 1.5), Float3::zero(), Float3::zero());
+        let toi = (0.55f32 / 1.5).acos();
+        let ca = ConservativeAdvancement::default();
+        let hit = ca.time_of_impact(&rod, &motion_a, &sphere, &motion_b, 2.0, &mut Frame3Simplex::new()).unwrap().unwrap();
+        //the step never passes the contact, and stops within `tolerance` of it at the rate the face closes in
+        assert!(hit.toi <= toi + 1e-5 && hit.toi >= toi - 2.0 * ca.tolerance, "{} instead of {}", hit.toi, toi);
+        assert!(ca.time_of_impact(&rod, &motion_a, &sphere, &motion_b, 1.0, &mut Frame3Simplex::new()).unwrap().is_none());
+    }
+}
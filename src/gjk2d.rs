@@ -0,0 +1,286 @@
+use crate::vector::{Float2, Scalar};
+use crate::simplex_based_cd_helper::*;
+use crate::support_map::SupportMap;
+type Point<S> = MinkowskiSumPoint<Float2<S>>;
+type Frame<S> = Frame3Simplex<Float2<S>>;
+type Result2<S> = Result<CollisionResult<Float2<S>>, Error>;
+
+const MAX_ITERATION: usize = 32;
+const MAX_EPA_ITERATION: usize = 64;
+/// EPA stops once the support point along the nearest edge normal is within
+/// `EPA_TOLERANCE_FACTOR * Scalar::TOLERANCE` of the edge, relative to its distance
+const EPA_TOLERANCE_FACTOR: f32 = 10.0;
+
+/// 2D counterpart of `gjk3d::check`, the simplex enclosing the origin is a triangle instead of a tetrahedron.
+pub fn check<S, A, B>(polya_:&A, polyb_:&B, ab_:&Float2<S>, f_:&mut Frame<S>) -> Result2<S>
+    where S: Scalar, A: SupportMap<Float2<S>> + ?Sized, B: SupportMap<Float2<S>> + ?Sized
+{
+    if !polya_.is_valid() || !polyb_.is_valid() { return Err(Error::InvalidInput) }
+    let one = Float2 {x:S::ONE, y:S::ONE};
+    let d = if ab_.is_zero() { &one } else { ab_ };
+    let mut s = vec![support(polya_, polyb_, d, f_)];
+    let mut w = Vec::with_capacity(3);
+    let mut iteration = 0;
+    let mut last = S::MAX;
+    let mut last_s = Vec::with_capacity(3);
+    let mut last_w = Vec::with_capacity(3);
+    loop {
+        let v = nearest_simplex(&mut s, &mut w);
+        let dist = v.sqr_magnitude();
+        if s.len() < 3 && dist >= last {
+            //rounding made the search step away from the origin, keep the previous sub-simplex
+            break Ok(CollisionResult::from_nearest_simplex(last_s, &last_w, iteration))
+        }
+        if s.len() == 3 { break epa(polya_, polyb_, f_, s, iteration) }
+        if encloses_origin(&s, &v) { break touching(polya_, polyb_, f_, s, w, iteration) }
+        let s3 = support(polya_, polyb_, &-v, f_);
+        //no further progress towards the origin
+        if dist - v.dot(&s3.v) <= dist * S::TOLERANCE || s.iter().any(|p| *p == s3 && p.v == s3.v) {
+            break Ok(CollisionResult::from_nearest_simplex(s, &w, iteration))
+        }
+        last = dist;
+        last_s.clone_from(&s);
+        last_w.clone_from(&w);
+        s.push(s3);
+        iteration += 1;
+        if iteration >= MAX_ITERATION { break Err(Error::NearestSimplexSearch) }
+    }
+}
+
+/// 2D counterpart of `gjk3d::signed_distance`.
+pub fn signed_distance<S, A, B>(polya_:&A, polyb_:&B, ab_:&Float2<S>, f_:&mut Frame<S>) -> Result<DistanceResult<Float2<S>>, Error>
+    where S: Scalar, A: SupportMap<Float2<S>> + ?Sized, B: SupportMap<Float2<S>> + ?Sized
+{
+    check(polya_, polyb_, ab_, f_).map(DistanceResult::from)
+}
+
+/// Reduces `s_` (1 to 3 points) to the sub-simplex holding the point nearest to the origin,
+/// writes the barycentric weights of that point into `w_` and returns it.
+/// All 3 points are kept only when the triangle encloses the origin.
+fn nearest_simplex<S>(s_:&mut Vec<Point<S>>, w_:&mut Vec<S>) -> Float2<S> where S: Scalar {
+    let w: Vec<S> = match s_.len() {
+        1 => vec![S::ONE],
+        2 => nearest_on_segment(&s_[0].v, &s_[1].v).to_vec(),
+        _ => nearest_on_triangle(&s_[0].v, &s_[1].v, &s_[2].v).to_vec(),
+    };
+    w_.clear();
+    let mut i = 0;
+    s_.retain(|_| { i += 1; w[i - 1] > S::ZERO });
+    w_.extend(w.iter().filter(|w| **w > S::ZERO));
+    let mut v = Float2::zero();
+    for (p, w) in s_.iter().zip(w_.iter()) {
+        v = v + p.v * *w;
+    }
+    v
+}
+
+/// The origin lies on the boundary of `A - B`, try to grow the point or segment into a triangle
+/// so EPA can report the contact normal.
+fn touching<S, A, B>(
+    polya_:&A, polyb_:&B, f_:&mut Frame<S>, mut s_:Vec<Point<S>>, w_:Vec<S>, iteration_:usize
+) -> Result2<S>
+    where S: Scalar, A: SupportMap<Float2<S>> + ?Sized, B: SupportMap<Float2<S>> + ?Sized
+{
+    let axis = if s_.len() == 2 { (s_[1].v - s_[0].v).perp() } else { Float2 {x:S::ONE, y:S::ZERO} };
+    for d in [axis, -axis] {
+        let s = support(polya_, polyb_, &d, f_);
+        if s.v.dot(&d) > S::ZERO && !s_.iter().any(|p| p.v == s.v) {
+            s_.push(s);
+            if s_.len() == 3 { return epa(polya_, polyb_, f_, s_, iteration_) }
+        }
+    }
+    s_.truncate(w_.len());
+    Ok(CollisionResult::from_nearest_simplex(s_, &w_, iteration_))
+}
+
+/// Expands the triangle enclosing the origin towards the boundary of `A - B`
+/// until the edge nearest to the origin is part of it.
+fn epa<S, A, B>(polya_:&A, polyb_:&B, f_:&mut Frame<S>, mut s_:Vec<Point<S>>, iteration_:usize) -> Result2<S>
+    where S: Scalar, A: SupportMap<Float2<S>> + ?Sized, B: SupportMap<Float2<S>> + ?Sized
+{
+    //keep the polygon counter clockwise so the edge normals point outwards
+    if (s_[1].v - s_[0].v).cross(&(s_[2].v - s_[0].v)) < S::ZERO { s_.swap(1, 2); }
+    f_.cache_edge.clear();
+    for i in 0..3 {
+        f_.cache_edge.push((s_[i].clone(), s_[(i + 1) % 3].clone()));
+    }
+    let mut iteration = 0;
+    loop {
+        let mut min = 0;
+        let mut dist = S::MAX;
+        let mut n = Float2::zero();
+        for (i, (a, b)) in f_.cache_edge.iter().enumerate() {
+            let e = (b.v - a.v).perp();
+            let m = e.magnitude();
+            if m == S::ZERO { continue }
+            let d = e.dot(&a.v) / m;
+            if d < dist {
+                dist = d;
+                min = i;
+                n = e.scaled(S::ONE, m);
+            }
+        }
+        if dist == S::MAX { break Err(Error::EPA) }
+        let s = support(polya_, polyb_, &n, f_);
+        let gap = n.dot(&s.v) - dist;
+        let (a, b) = f_.cache_edge[min].clone();
+        let converged = gap <= dist.max(S::ZERO) * S::TOLERANCE * S::from_f32(EPA_TOLERANCE_FACTOR) || s.v == a.v || s.v == b.v;
+        //as in 3D, polygons with many short edges may not settle in time, report the nearest edge as not converged
+        if converged || iteration >= MAX_EPA_ITERATION {
+            let w = nearest_on_segment(&a.v, &b.v);
+            let scale = a.v.magnitude().max(b.v.magnitude());
+            break Ok(CollisionResult::from_penetration(
+                a.pa * w[0] + b.pa * w[1], a.pb * w[0] + b.pb * w[1], n, dist, scale, s_, iteration_ + iteration, converged,
+            ))
+        }
+        f_.cache_edge[min] = (a, s.clone());
+        f_.cache_edge.insert(min + 1, (s, b));
+        iteration += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// deterministic numbers in [0, 1)
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> f32 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 40) as f32 / (1u64 << 24) as f32
+        }
+
+        /// convex polygon of 3 to 8 vertices, counter clockwise, centered in `[0, scale_]^2`
+        fn polygon(&mut self, scale_:f32) -> Vec<Float2> {
+            let center = Float2 {x:self.next() * scale_, y:self.next() * scale_};
+            let count = 3 + (self.next() * 6.0) as usize;
+            let (start, radius) = (self.next() * std::f32::consts::TAU, 0.5 + self.next());
+            (0..count).map(|k| {
+                let (s, c) = (start + k as f32 * std::f32::consts::TAU / count as f32).sin_cos();
+                center + Float2 {x:c, y:s} * radius
+            }).collect()
+        }
+    }
+
+    fn square(center_:Float2, half_:f32) -> Vec<Float2> {
+        [(-1.0, -1.0), (1.0, -1.0), (1.0, 1.0), (-1.0, 1.0)].iter().map(|(x, y)| center_ + Float2 {x:*x * half_, y:*y * half_}).collect()
+    }
+
+    fn query(a_:&[Float2], b_:&[Float2]) -> CollisionResult<Float2> {
+        check(a_, b_, &Float2::zero(), &mut Frame3Simplex::new()).unwrap()
+    }
+
+    /// witness points one distance apart along the normal
+    fn assert_witness(r_:&CollisionResult<Float2>) {
+        assert!((r_.closest_a + r_.normal * r_.distance - r_.closest_b).magnitude() <= 1e-4, "{:?}", r_);
+    }
+
+    #[test]
+    fn squares() {
+        let a = square(Float2::zero(), 1.0);
+        //apart along x, then diagonally corner to corner
+        let r = query(&a, &square(Float2 {x:3.0, y:0.5}, 1.0));
+        assert_eq!(r.status, CollisionStatus::Disjoint);
+        assert!((r.distance - 1.0).abs() <= 1e-5 && (r.normal - Float2 {x:1.0, y:0.0}).magnitude() <= 1e-5);
+        assert_witness(&r);
+        let r = query(&a, &square(Float2 {x:3.0, y:3.0}, 1.0));
+        assert!((r.distance - 2f32.sqrt()).abs() <= 1e-5);
+        assert!((r.normal - Float2 {x:1.0, y:1.0} * 0.5f32.sqrt()).magnitude() <= 1e-5);
+        assert_witness(&r);
+        //edge to edge
+        let r = query(&a, &square(Float2 {x:2.0, y:0.3}, 1.0));
+        assert_eq!(r.status, CollisionStatus::Touching);
+        assert!(r.distance.abs() <= 1e-5);
+        //overlapping by 0.5 along x, 1.8 along y
+        let r = query(&a, &square(Float2 {x:1.5, y:0.2}, 1.0));
+        assert_eq!(r.status, CollisionStatus::Penetrating);
+        assert!((r.depth() - 0.5).abs() <= 1e-5 && (r.normal - Float2 {x:1.0, y:0.0}).magnitude() <= 1e-5);
+        assert!(r.converged);
+        assert_witness(&r);
+    }
+
+    /// signed distance between convex counter clockwise polygons: the largest gap along their edge normals,
+    /// which is the separation only when the nearest features are an edge and a vertex
+    fn separating_axis(a_:&[Float2], b_:&[Float2]) -> f32 {
+        let mut ret = f32::MIN;
+        for (p, q) in [(a_, b_), (b_, a_)] {
+            for k in 0..p.len() {
+                let mut n = (p[(k + 1) % p.len()] - p[k]).perp();
+                n = n * (1.0 / n.magnitude());
+                let lo = q.iter().map(|v| v.dot(&n)).fold(f32::MAX, f32::min);
+                ret = ret.max(lo - p[k].dot(&n));
+            }
+        }
+        ret
+    }
+
+    /// distance from `p_` to the segment `a_`, `b_`
+    fn point_segment(p_:&Float2, a_:&Float2, b_:&Float2) -> f32 {
+        let e = *b_ - *a_;
+        let t = ((*p_ - *a_).dot(&e) / e.dot(&e)).clamp(0.0, 1.0);
+        (*a_ + e * t - *p_).magnitude()
+    }
+
+    /// distance between disjoint convex polygons, reached from a vertex of one to an edge of the other
+    fn polygon_distance(a_:&[Float2], b_:&[Float2]) -> f32 {
+        let mut ret = f32::MAX;
+        for (p, q) in [(a_, b_), (b_, a_)] {
+            for v in p {
+                for k in 0..q.len() {
+                    ret = ret.min(point_segment(v, &q[k], &q[(k + 1) % q.len()]));
+                }
+            }
+        }
+        ret
+    }
+
+    #[test]
+    fn random_polygons() {
+        let mut rng = Lcg(3);
+        let (mut apart, mut overlapping) = (0, 0);
+        for _ in 0..2000 {
+            let a = rng.polygon(3.0);
+            let b = rng.polygon(3.0);
+            let sat = separating_axis(&a, &b);
+            if sat.abs() < 1e-3 { continue }
+            let r = query(&a, &b);
+            assert_witness(&r);
+            if sat > 0.0 {
+                apart += 1;
+                assert_eq!(r.status, CollisionStatus::Disjoint);
+                assert!((r.distance - polygon_distance(&a, &b)).abs() <= 1e-4, "{} instead of {}", r.distance, polygon_distance(&a, &b));
+            }
+            else {
+                overlapping += 1;
+                assert_eq!(r.status, CollisionStatus::Penetrating);
+                assert!((r.depth() + sat).abs() <= 1e-4, "{} instead of {}", r.depth(), -sat);
+                //moving B along the normal by the depth leaves the polygons touching
+                let moved: Vec<Float2> = b.iter().map(|v| *v + r.normal * r.depth()).collect();
+                assert!(separating_axis(&a, &moved).abs() <= 1e-4);
+            }
+        }
+        assert!(apart > 100 && overlapping > 100);
+    }
+
+    /// disc whose radius grows a little on every support query, so EPA never sees the boundary settle
+    struct GrowingDisc(std::cell::Cell<f32>);
+
+    impl SupportMap<Float2> for GrowingDisc {
+        fn support(&self, d_:&Float2) -> (Float2, crate::support_map::FeatureId) {
+            let radius = self.0.get();
+            self.0.set(radius + 1e-3);
+            (d_.scaled(radius, d_.magnitude()), 0)
+        }
+    }
+
+    #[test]
+    fn epa_iteration_cap() {
+        let a = GrowingDisc(std::cell::Cell::new(1.0));
+        let r = check(&a, &square(Float2 {x:0.5, y:0.0}, 0.5)[..], &Float2::zero(), &mut Frame3Simplex::new()).unwrap();
+        assert_eq!(r.status, CollisionStatus::Penetrating);
+        assert!(!r.converged);
+        assert!(r.iteration >= MAX_EPA_ITERATION);
+    }
+}
@@ -1,126 +1,740 @@
-use crate::vector::Float3;
+use crate::vector::{Float3, Scalar};
 use crate::simplex_based_cd_helper::*;
-type Point = MinkowskiSumPoint<Float3>;
-type Frame = Frame3Simplex<Float3>;
+use crate::support_map::SupportMap;
+type Point<S> = MinkowskiSumPoint<Float3<S>>;
+type Frame<S> = Frame3Simplex<Float3<S>>;
+type Result3<S> = Result<CollisionResult<Float3<S>>, Error>;
 
-const MAX_ITERATION: i32 = 32;
+const MAX_ITERATION: usize = 32;
+/// curved shapes close the gap to the nearest point a fraction at a time, so the nearest simplex search gets more room
+const MAX_NEAREST_ITERATION: usize = 64;
+const MAX_EPA_ITERATION: usize = 128;
+const MAX_RAY_ITERATION: usize = 64;
+/// EPA stops once the support point along the nearest face normal is within
+/// `EPA_TOLERANCE_FACTOR * Scalar::TOLERANCE` of the face, relative to its distance
+const EPA_TOLERANCE_FACTOR: f32 = 10.0;
 
-pub fn check(polya_:&[Float3], polyb_:&[Float3], ab_:&Float3, f_:&mut Frame) -> Result<bool, Error> {
-    if polya_.len() < 4 || polyb_.len() < 4 { return Err(Error::InvalidInput) }
+/// Tests `polya_` against `polyb_`, `ab_` being a hint for the direction from B towards A.
+pub fn check<S, A, B>(polya_:&A, polyb_:&B, ab_:&Float3<S>, f_:&mut Frame<S>) -> Result3<S>
+    where S: Scalar, A: SupportMap<Float3<S>> + ?Sized, B: SupportMap<Float3<S>> + ?Sized
+{
+    if !polya_.is_valid() || !polyb_.is_valid() { return Err(Error::InvalidInput) }
     let mut iteration = 0;
-    let d = if ab_.is_zero() { &Float3 {x:1.0,y:1.0,z:1.0} } else { ab_ };
-    let mut s0 = support(polya_, polyb_, &d, f_);
+    let one = Float3 {x:S::ONE, y:S::ONE, z:S::ONE};
+    let d = if ab_.is_zero() { &one } else { ab_ };
+    let mut s0 = support(polya_, polyb_, d, f_);
     let mut s1 = support(polya_, polyb_, &-s0.v, f_);
+    //a support point short of the origin separates the shapes, and may repeat an earlier one, leaving no triangle
+    if s1.v.dot(&s0.v) > S::ZERO { return no_collision(polya_, polyb_, f_, vec![s1], iteration) }
     let d = s1.v - s0.v;
     let d = plane_normal(&d, &-s0.v);
     let mut s2 = support(polya_, polyb_, &d, f_);
+    if s2.v.dot(&d) < S::ZERO { return no_collision(polya_, polyb_, f_, vec![s0, s1, s2], iteration) }
     let mut d = Float3::triangle_normal(&s0.v, &s1.v, &s2.v);
-    if d.dot(&s0.v) > 0.0 {
+    if d.dot(&s0.v) > S::ZERO {
         std::mem::swap(&mut s1, &mut s2);
-        d=-d;
+        d = -d;
     }
-    let mut s3: Point;
+    //size of `A - B` the support points have shown so far
+    let mut scale = [&s0, &s1, &s2].iter().fold(S::ZERO, |m, p| m.max(p.v.sqr_magnitude()));
+    let mut s3: Point<S>;
     loop {
         s3 = support(polya_, polyb_, &d, f_);
-        println!("{}\n{} {} {} {}\n{}", d, s0, s1, s2, s3, s3.v.dot(&d));
-        if s3.v.dot(&d) < 0.0 {
-            break no_collision(polya_, polyb_, f_, s0, s1, s2, s3, d)
+        //nothing past the plane through the origin up to rounding, it lies on the boundary at most:
+        //the tetrahedron would be flat, and curved shapes resting on each other would never enclose the origin
+        scale = scale.max(s3.v.sqr_magnitude());
+        if s3.v.dot(&d) <= d.magnitude() * scale.sqrt() * S::TOLERANCE {
+            break no_collision(polya_, polyb_, f_, vec![s0, s1, s2], iteration)
         }
         let n0 = Float3::triangle_normal(&s0.v, &s1.v, &s3.v);
         let n1 = Float3::triangle_normal(&s2.v, &s0.v, &s3.v);
         let n2 = Float3::triangle_normal(&s1.v, &s2.v, &s3.v);
-        let d0 = s3.v.dot(&n0);
-        let d1 = s3.v.dot(&n1);
-        let d2 = s3.v.dot(&n2);
-        println!("{} {} {} {} {} {}", n0, n1, n2, d0, d1, d2);
-        if d0 >= 0.0 && d1 >= 0.0 && d2 >= 0.0 {
-            break epa(polya_, polyb_, f_, s0, s1, s2, s3);
-        }
-        if d0 < 0.0 {
+        //the origin on a face up to rounding is enclosed: the first support points of two spheres along the line
+        //through their centers are in line with it, and every face keeping them would leave it on either side by turns
+        let rounding = scale.sqrt() * S::TOLERANCE;
+        let d0 = s3.v.dot(&n0) + n0.magnitude() * rounding;
+        let d1 = s3.v.dot(&n1) + n1.magnitude() * rounding;
+        let d2 = s3.v.dot(&n2) + n2.magnitude() * rounding;
+        if d0 >= S::ZERO && d1 >= S::ZERO && d2 >= S::ZERO {
+            break epa(polya_, polyb_, f_, s0, s1, s2, s3, iteration);
+        }
+        if d0 < S::ZERO {
             s2 = s3;
             d = n0;
         }
-        else if d1 < 0.0 {
+        else if d1 < S::ZERO {
             s1 = s3;
             d = n1;
         }
-        else if d2 < 0.0 {
+        else if d2 < S::ZERO {
             s0 = s3;
             d = n2;
         }
         iteration += 1;
-        if iteration >= MAX_ITERATION { break Err(Error::SimplexSearch) }
+        //curved shapes barely overlapping keep the tetrahedron short of the origin, let the nearest simplex search settle it
+        if iteration >= MAX_ITERATION { break no_collision(polya_, polyb_, f_, vec![s0, s1, s2], iteration) }
     }
 }
 
-fn no_collision(
-    polya_:&[Float3], polyb_:&[Float3], f_:&mut Frame,
-    mut s0:Point, mut s1:Point, mut s2:Point, mut s3:Point, mut d:Float3
-) -> Result<bool, Error> {
-    println!("nearest simplex");
+/// `check` for objects moving little between calls, `c_` being the frame kept for this pair of objects.
+///
+/// While the pair stays apart the query starts from the last simplex, re-evaluated at the current position of its
+/// features when both shapes implement `SupportMap::feature_point`, or else from the last separating direction,
+/// and only refines it; otherwise it runs `check` with the last normal as hint.
+pub fn check_coherent<S, A, B>(polya_:&A, polyb_:&B, ab_:&Float3<S>, c_:&mut CoherentFrame<Float3<S>>) -> Result3<S>
+    where S: Scalar, A: SupportMap<Float3<S>> + ?Sized, B: SupportMap<Float3<S>> + ?Sized
+{
+    let last = c_.last.take();
+    let ret = match &last {
+        Some(r) if r.status == CollisionStatus::Disjoint && !r.normal.is_zero() => {
+            if !polya_.is_valid() || !polyb_.is_valid() { return Err(Error::InvalidInput) }
+            let mut s: Vec<Point<S>> = r.simplex.iter().filter_map(|p| {
+                let (pa, pb) = (polya_.feature_point(p.a)?, polyb_.feature_point(p.b)?);
+                Some(MinkowskiSumPoint {v:pa - pb, pa, pb, a:p.a, b:p.b})
+            }).collect();
+            //the point of `A - B` nearest to the origin lies along the normal from A towards B
+            if s.len() < r.simplex.len() {
+                s.clear();
+                s.push(support(polya_, polyb_, &r.normal, &mut c_.frame));
+            }
+            let mut w = Vec::with_capacity(4);
+            let iteration = nearest_simplex_search(polya_, polyb_, &mut c_.frame, &mut s, &mut w)?;
+            let ret = CollisionResult::from_nearest_simplex(s, &w, iteration);
+            //the search stops as soon as it encloses the origin, let `check` measure the penetration
+            if ret.is_colliding() { check(polya_, polyb_, &-r.normal, &mut c_.frame) } else { Ok(ret) }
+        },
+        Some(r) if !r.normal.is_zero() => check(polya_, polyb_, &-r.normal, &mut c_.frame),
+        _ => check(polya_, polyb_, ab_, &mut c_.frame),
+    };
+    c_.last = ret.as_ref().ok().cloned();
+    ret
+}
+
+/// Signed distance between `polya_` and `polyb_`: positive separation when apart, negative penetration depth
+/// when overlapping, with the normal always pointing from A towards B. See `DistanceResult`.
+pub fn signed_distance<S, A, B>(polya_:&A, polyb_:&B, ab_:&Float3<S>, f_:&mut Frame<S>) -> Result<DistanceResult<Float3<S>>, Error>
+    where S: Scalar, A: SupportMap<Float3<S>> + ?Sized, B: SupportMap<Float3<S>> + ?Sized
+{
+    check(polya_, polyb_, ab_, f_).map(DistanceResult::from)
+}
+
+/// The tetrahedron search proved the origin lies outside `A - B`,
+/// refine the simplex until it holds the point of `A - B` nearest to the origin.
+fn no_collision<S, A, B>(
+    polya_:&A, polyb_:&B, f_:&mut Frame<S>,
+    s_:Vec<Point<S>>, iteration_:usize
+) -> Result3<S>
+    where S: Scalar, A: SupportMap<Float3<S>> + ?Sized, B: SupportMap<Float3<S>> + ?Sized
+{
+    let mut s = s_;
+    let mut w = Vec::with_capacity(4);
+    let iteration = nearest_simplex_search(polya_, polyb_, f_, &mut s, &mut w)?;
+    //the origin was just inside after all, and the search found a tetrahedron around it
+    if let [s0, s1, s2, s3] = s.as_slice() {
+        return epa(polya_, polyb_, f_, s0.clone(), s1.clone(), s2.clone(), s3.clone(), iteration_ + iteration)
+    }
+    Ok(CollisionResult::from_nearest_simplex(s, &w, iteration_ + iteration))
+}
+
+/// Grows and reduces `s_` until it holds the point of `A - B` nearest to the origin,
+/// leaving its barycentric weights in `w_`, and returns the iterations spent.
+/// Stops early with the origin enclosed when the shapes overlap.
+fn nearest_simplex_search<S, A, B>(
+    polya_:&A, polyb_:&B, f_:&mut Frame<S>, s_:&mut Vec<Point<S>>, w_:&mut Vec<S>
+) -> Result<usize, Error>
+    where S: Scalar, A: SupportMap<Float3<S>> + ?Sized, B: SupportMap<Float3<S>> + ?Sized
+{
     let mut iteration = 0;
-    while d.dot(&(s3.v - s0.v)) > 0.0 {
-        let n0 = Float3::triangle_normal(&s0.v, &s1.v, &s3.v);
-        let n1 = Float3::triangle_normal(&s3.v, &s1.v, &s2.v);
-        let n2 = Float3::triangle_normal(&s0.v, &s3.v, &s2.v);
-        let d0 = n0.dot(&s3.v);
-        let d0 = if d0 < 0.0 { 0.0 } else { d0 };
-        let d1 = n1.dot(&s3.v);
-        let d1 = if d1 < 0.0 { 0.0 } else { d1 };
-        let d2 = n2.dot(&s3.v);
-        let d2 = if d2 < 0.0 { 0.0 } else { d2 };
-        let d0 = -(d0 * d0) / n0.sqr_magnitude();
-        let d1 = -(d1 * d1) / n1.sqr_magnitude();
-        let d2 = -(d2 * d2) / n2.sqr_magnitude();
-        println!("{} {} {}", d0, d1, d2);
-        if d0 <= d1 && d0 <= d2 {
-            s2 = s3;
+    let mut last = S::MAX;
+    let mut last_s = Vec::with_capacity(4);
+    let mut last_w = Vec::with_capacity(4);
+    //size of `A - B` the support points have shown so far
+    let mut scale = s_.iter().fold(S::ZERO, |m, p| m.max(p.v.sqr_magnitude()));
+    loop {
+        let v = nearest_simplex(s_, w_);
+        let dist = v.sqr_magnitude();
+        if dist >= last {
+            //rounding made the search step away from the origin, keep the previous sub-simplex
+            //unless the new one is as near, its point is then the more accurate one
+            if dist > last {
+                std::mem::swap(s_, &mut last_s);
+                std::mem::swap(w_, &mut last_w);
+            }
+            break Ok(iteration)
         }
-        else if d1 <= d0 && d1 <= d2 {
-            s0 = s3;
+        if s_.len() == 4 || encloses_origin(s_, &v) { break Ok(iteration) }
+        last = dist;
+        last_s.clone_from(s_);
+        last_w.clone_from(w_);
+        let s3 = support(polya_, polyb_, &-v, f_);
+        scale = scale.max(s3.v.sqr_magnitude());
+        //no further progress towards the origin: the distance is known relative to itself or to the size of `A - B`,
+        //up to the rounding of support points far from the world origin
+        let slack = v.magnitude() * (scale.sqrt() * S::TOLERANCE + s3.pa.magnitude().max(s3.pb.magnitude()) * S::EPSILON * S::from_f32(4.0));
+        if dist - v.dot(&s3.v) <= dist * S::TOLERANCE + slack || s_.iter().any(|p| *p == s3 && p.v == s3.v) { break Ok(iteration) }
+        s_.push(s3);
+        iteration += 1;
+        if iteration >= MAX_NEAREST_ITERATION { break Err(Error::NearestSimplexSearch) }
+    }
+}
+
+/// Separation between `polya_` and `polyb_`, see `DistanceResult` for what is reported on overlap.
+pub fn distance<S, A, B>(polya_:&A, polyb_:&B, f_:&mut Frame<S>) -> Result<DistanceResult<Float3<S>>, Error>
+    where S: Scalar, A: SupportMap<Float3<S>> + ?Sized, B: SupportMap<Float3<S>> + ?Sized
+{
+    if !polya_.is_valid() || !polyb_.is_valid() { return Err(Error::InvalidInput) }
+    let d = Float3 {x:S::ONE, y:S::ZERO, z:S::ZERO};
+    let mut s = vec![support(polya_, polyb_, &d, f_)];
+    let mut w = Vec::with_capacity(4);
+    let iteration = nearest_simplex_search(polya_, polyb_, f_, &mut s, &mut w)?;
+    Ok(CollisionResult::from_nearest_simplex(s, &w, iteration).into())
+}
+
+/// Casts the ray `origin_ + dir_ * t`, `t` in `[0, 1]`, against `poly_` and returns the first hit if any.
+/// A ray starting inside the shape hits at fraction zero with a zero normal.
+pub fn ray_cast<S, A>(poly_:&A, origin_:&Float3<S>, dir_:&Float3<S>, f_:&mut Frame<S>) -> Result<Option<RayHit<Float3<S>>>, Error>
+    where S: Scalar, A: SupportMap<Float3<S>> + ?Sized
+{
+    if !poly_.is_valid() { return Err(Error::InvalidInput) }
+    //`origin_ + dir_ * t` is inside A when the origin is inside `A - origin_ - dir_ * t`
+    Ok(sweep(poly_, std::slice::from_ref(origin_), dir_, f_)?.map(|h| RayHit {
+        fraction: h.fraction,
+        point: *origin_ + *dir_ * h.fraction,
+        normal: h.normal,
+    }))
+}
+
+/// Moves `polya_` by `va_ * t` and `polyb_` by `vb_ * t` for `t` in `[0, dt_]` and returns the first time they touch.
+/// Shapes overlapping from the start hit at time zero with a zero normal.
+pub fn shape_cast<S, A, B>(
+    polya_:&A, va_:&Float3<S>, polyb_:&B, vb_:&Float3<S>, dt_:S, f_:&mut Frame<S>
+) -> Result<Option<ShapeCastHit<Float3<S>>>, Error>
+    where S: Scalar, A: SupportMap<Float3<S>> + ?Sized, B: SupportMap<Float3<S>> + ?Sized
+{
+    if !polya_.is_valid() || !polyb_.is_valid() { return Err(Error::InvalidInput) }
+    //the shapes touch at `t` when the origin is on `A - B + (va - vb) * t`
+    let dir = (*vb_ - *va_) * dt_;
+    Ok(sweep(polya_, polyb_, &dir, f_)?.map(|h| {
+        let toi = h.fraction * dt_;
+        ShapeCastHit {
+            toi,
+            normal: h.normal,
+            closest_a: h.closest_a + *va_ * toi,
+            closest_b: h.closest_b + *vb_ * toi,
         }
-        else if d2 <= d0 && d2 <= d1 {
-            s1 = s3;
+    }))
+}
+
+/// Where `sweep` hit `A - B`, witness points are taken before moving the shapes.
+struct Sweep<S> where S: Scalar {
+    fraction: S,
+    normal: Float3<S>,
+    closest_a: Float3<S>,
+    closest_b: Float3<S>,
+}
+
+/// Moves the point `x = dir_ * t` from the origin for `t` in `[0, 1]` and returns where it first enters `A - B`.
+fn sweep<S, A, B>(polya_:&A, polyb_:&B, dir_:&Float3<S>, f_:&mut Frame<S>) -> Result<Option<Sweep<S>>, Error>
+    where S: Scalar, A: SupportMap<Float3<S>> + ?Sized, B: SupportMap<Float3<S>> + ?Sized
+{
+    let mut fraction = S::ZERO;
+    let mut x = Float3::zero();
+    let mut n = Float3::zero();
+    //the simplex lives on `A - B - x`, its points are shifted whenever `x` moves along the ray
+    let mut s = vec![support(polya_, polyb_, dir_, f_)];
+    let mut w = Vec::with_capacity(4);
+    let mut scale = s[0].v.sqr_magnitude();
+    let mut last = S::MAX;
+    let mut iteration = 0;
+    loop {
+        for p in s.iter_mut() {
+            p.v = p.pa - p.pb - x;
         }
-        else {
-            println!("unexpected {} {} {}", d0, d1, d2);
+        let v = nearest_simplex(&mut s, &mut w);
+        let dist = v.sqr_magnitude();
+        //either `x` reached the surface or rounding keeps the search from getting any closer
+        if s.len() == 4 || dist <= scale * S::TOLERANCE * S::TOLERANCE || dist >= last {
+            let mut hit = Sweep {
+                fraction,
+                normal: if n.is_zero() { n } else { n.scaled(-S::ONE, n.magnitude()) },
+                closest_a: Float3::zero(),
+                closest_b: Float3::zero(),
+            };
+            for (p, w) in s.iter().zip(w.iter()) {
+                hit.closest_a = hit.closest_a + p.pa * *w;
+                hit.closest_b = hit.closest_b + p.pb * *w;
+            }
+            break Ok(Some(hit))
         }
-        d = Float3::triangle_normal(&s0.v, &s1.v, &s2.v);
-        s3 = support(polya_, polyb_, &d, f_);
+        last = dist;
+        let mut p = support(polya_, polyb_, &-v, f_);
+        p.v = p.v - x;
+        let vw = v.dot(&p.v);
+        if vw > S::ZERO {
+            //`v` separates `x` from `A - B`, jump to where the ray crosses that plane
+            let vr = v.dot(dir_);
+            if vr <= S::ZERO { break Ok(None) }
+            fraction = fraction + vw / vr;
+            if fraction > S::ONE { break Ok(None) }
+            x = *dir_ * fraction;
+            n = v;
+            last = S::MAX;
+        }
+        scale = scale.max(p.v.sqr_magnitude());
+        if !s.iter().any(|q| *q == p && q.pa == p.pa && q.pb == p.pb) { s.push(p); }
         iteration += 1;
-        if iteration >= MAX_ITERATION { return Err(Error::NearestSimplexSearch) }
+        if iteration >= MAX_RAY_ITERATION { break Err(Error::RayCast) }
     }
-    calculate_mtv_from_nearest_feature(polya_, polyb_, f_, s0, s1, s2, &d)
 }
 
-fn epa(
-    polya_:&[Float3], polyb_:&[Float3], f_:&mut Frame,
-    s0:Point, s1:Point, s2:Point, mut s3:Point
-) -> Result<bool, Error> {
+/// Expands the tetrahedron enclosing the origin towards the boundary of `A - B`
+/// until the face nearest to the origin is part of it.
+#[allow(clippy::too_many_arguments)]
+fn epa<S, A, B>(
+    polya_:&A, polyb_:&B, f_:&mut Frame<S>,
+    s0:Point<S>, s1:Point<S>, s2:Point<S>, s3:Point<S>, iteration_:usize
+) -> Result3<S>
+    where S: Scalar, A: SupportMap<Float3<S>> + ?Sized, B: SupportMap<Float3<S>> + ?Sized
+{
+    //wind the faces so their normals point away from the opposite vertex
+    let (s1, s2) = if Float3::triangle_normal(&s0.v, &s1.v, &s2.v).dot(&(s3.v - s0.v)) > S::ZERO { (s2, s1) } else { (s1, s2) };
     f_.cache_epa.clear();
-    f_.cache_epa.push(EPA2Simplex::new(&s0, &s2, &s1));
-    f_.cache_epa.push(EPA2Simplex::new(&s0, &s1, &s3));
-    f_.cache_epa.push(EPA2Simplex::new(&s1, &s2, &s3));
-    f_.cache_epa.push(EPA2Simplex::new(&s2, &s0, &s3));
+    f_.cache_epa.push(EPA2Simplex::new(&s0, &s1, &s2));
+    f_.cache_epa.push(EPA2Simplex::new(&s0, &s3, &s1));
+    f_.cache_epa.push(EPA2Simplex::new(&s1, &s3, &s2));
+    f_.cache_epa.push(EPA2Simplex::new(&s2, &s3, &s0));
+    let simplex = vec![s0, s1, s2, s3];
+    //size of `A - B` the support points have shown so far
+    let mut scale = simplex.iter().fold(S::ZERO, |m, p| m.max(p.v.sqr_magnitude()));
     let mut iteration = 0;
-    let mut min = 0;
-    let mut dist;
     loop {
-        dist = std::f32::MAX;
+        let mut min = 0;
+        let mut dist = S::MAX;
         for (p, e) in f_.cache_epa.iter().enumerate() {
-            if e.d_sqr < dist {
-                dist = e.d_sqr;
+            if e.d < dist {
+                dist = e.d;
                 min = p;
             }
         }
+        if dist == S::MAX { break Err(Error::EPA) }
         let simplex_m = f_.cache_epa[min].clone();
-        let n = &simplex_m.n;
-        s3 = support(polya_, polyb_, n, f_);
-        if n.dot(&(s3.v - simplex_m.v0.v)) <= 0.0 {
-            f_.mtv_from_epa(&simplex_m, polya_, polyb_);
-            break Ok(true)
+        let s = support(polya_, polyb_, &simplex_m.n, f_);
+        let gap = simplex_m.n.dot(&s.v) - dist;
+        scale = scale.max(s.v.sqr_magnitude());
+        //the depth is known relative to itself, or to the size of `A - B` when the shapes barely overlap
+        let converged = gap <= dist.max(S::ZERO) * S::TOLERANCE * S::from_f32(EPA_TOLERANCE_FACTOR) + scale.sqrt() * S::TOLERANCE
+            || !simplex_m.is_visible(&s.v);
+        //curved shapes deep inside each other leave no face to converge on, report the nearest one as not converged
+        if converged || iteration >= MAX_EPA_ITERATION {
+            //a flat side of `A - B` is split into coplanar faces, report from the one the origin projects into
+            let limit = dist + (dist.abs() + simplex_m.v0.v.magnitude()) * S::TOLERANCE;
+            let face = f_.cache_epa.iter()
+                .filter(|e| e.d <= limit && e.contains_projection())
+                .min_by(|a, b| a.d.partial_cmp(&b.d).unwrap_or(std::cmp::Ordering::Equal))
+                .cloned()
+                .unwrap_or(simplex_m);
+            break Ok(CollisionResult::from_epa(&face, simplex, iteration_ + iteration, converged))
         }
+        expand(f_, &s);
         iteration += 1;
-        if iteration >= MAX_ITERATION { break Err(Error::EPA) }
     }
-}
\ No newline at end of file
+}
+
+/// Removes every face `s_` can see and stitches the horizon edges left behind to `s_`.
+fn expand<S>(f_:&mut Frame<S>, s_:&Point<S>) where S: Scalar {
+    f_.cache_edge.clear();
+    let mut i = 0;
+    while i < f_.cache_epa.len() {
+        if !f_.cache_epa[i].is_visible(&s_.v) {
+            i += 1;
+            continue
+        }
+        let e = f_.cache_epa.swap_remove(i);
+        for (a, b) in [(e.v0.clone(), e.v1.clone()), (e.v1.clone(), e.v2.clone()), (e.v2, e.v0)] {
+            //an edge shared by two visible faces is interior to the hole
+            match f_.cache_edge.iter().position(|(ea, eb)| ea.v == b.v && eb.v == a.v) {
+                Some(p) => { f_.cache_edge.swap_remove(p); }
+                None => f_.cache_edge.push((a, b)),
+            }
+        }
+    }
+    for (a, b) in &f_.cache_edge {
+        f_.cache_epa.push(EPA2Simplex::new(a, b, s_));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::{Capsule, OrientedBox, Sphere};
+
+    /// deterministic numbers in [0, 1)
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> f32 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 40) as f32 / (1u64 << 24) as f32
+        }
+
+        fn point(&mut self, scale_:f32) -> Float3 {
+            Float3 {x:self.next() * scale_, y:self.next() * scale_, z:self.next() * scale_}
+        }
+
+        /// orthonormal axes of a random rotation
+        fn axes(&mut self) -> [Float3; 3] {
+            let half = Float3 {x:0.5, y:0.5, z:0.5};
+            let mut a = self.point(1.0) - half;
+            a.normalize();
+            let mut b = self.point(1.0) - half;
+            b = b - a * a.dot(&b);
+            b.normalize();
+            [a, b, a.cross(&b)]
+        }
+
+        fn oriented_box(&mut self, scale_:f32) -> OrientedBox {
+            let half_extents = self.point(1.0) + Float3 {x:0.2, y:0.2, z:0.2};
+            OrientedBox {center:self.point(scale_), axes:self.axes(), half_extents}
+        }
+    }
+
+    #[test]
+    fn boxes_apart() {
+        //the first support points often prove such boxes apart before any triangle is built
+        let mut rng = Lcg(7);
+        let mut count = 0;
+        while count < 2000 {
+            let (a, b) = (rng.oriented_box(4.0), rng.oriented_box(4.0));
+            if (a.center - b.center).magnitude() <= a.half_extents.magnitude() + b.half_extents.magnitude() { continue }
+            count += 1;
+            let hint = rng.point(1.0) - Float3 {x:0.5, y:0.5, z:0.5};
+            for ab in [a.center - b.center, hint, Float3 {x:1.0, y:0.0, z:0.0}] {
+                let r = check(&a, &b, &ab, &mut Frame3Simplex::new()).unwrap();
+                assert!(!r.is_colliding());
+            }
+        }
+    }
+
+    /// corners of the unit cube `[-1, 0] x [-1, 0] x [0, 1]` moved by `offset_`, as in the input file
+    fn cube(offset_:Float3) -> Vec<Float3> {
+        (0..8).map(|i| offset_ + Float3 {x:-((i & 1) as f32), y:-(((i >> 1) & 1) as f32), z:((i >> 2) & 1) as f32}).collect()
+    }
+
+    #[test]
+    fn cubes_touching_face_to_face() {
+        //support points along the contact normal all lie on the shared face, leaving a flat tetrahedron
+        let a = cube(Float3 {x:2.0, y:0.0, z:0.0});
+        for k in 0..20 {
+            let b = cube(Float3 {x:3.0, y:k as f32 * 0.1 - 1.0, z:0.0});
+            for ab in [Float3 {x:-1.0, y:0.0, z:0.0}, Float3 {x:1.0, y:0.0, z:0.0}, Float3 {x:0.0, y:1.0, z:0.0}, Float3 {x:0.3, y:0.5, z:0.1}] {
+                assert!(check(&a, &b, &ab, &mut Frame3Simplex::new()).is_ok());
+            }
+        }
+    }
+
+    #[test]
+    fn touching_depth_is_positive_zero() {
+        let a = cube(Float3 {x:2.0, y:0.0, z:0.0});
+        let b = cube(Float3 {x:3.0, y:0.0, z:0.0});
+        let mut touching = 0;
+        for ab in [Float3 {x:-1.0, y:0.0, z:0.0}, Float3 {x:0.0, y:1.0, z:0.0}, Float3 {x:0.3, y:0.5, z:0.1}] {
+            let r = check(&a, &b, &ab, &mut Frame3Simplex::new()).unwrap();
+            if r.status != CollisionStatus::Touching { continue }
+            touching += 1;
+            //-0 would print as a negative depth
+            assert!(r.depth().is_sign_positive());
+            if r.depth() == 0.0 { assert!(r.distance.is_sign_positive()); }
+        }
+        assert!(touching > 0);
+    }
+
+    #[test]
+    fn spheres_barely_apart() {
+        //the nearest simplex search closes in on the nearest points of curved shapes a fraction at a time
+        let mut rng = Lcg(17);
+        for _ in 0..500 {
+            let mut dir = rng.point(1.0) - Float3 {x:0.5, y:0.5, z:0.5};
+            dir.normalize();
+            let a = Sphere {center:rng.point(1.0), radius:0.2 + rng.next()};
+            let radius = 0.2 + rng.next();
+            let b = Sphere {center:a.center + dir * (a.radius + radius + 1e-3), radius};
+            let r = check(&a, &b, &(a.center - b.center), &mut Frame3Simplex::new()).unwrap();
+            assert!(!r.is_colliding());
+            assert!((distance(&a, &b, &mut Frame3Simplex::new()).unwrap().distance - 1e-3).abs() < 1e-4);
+        }
+    }
+
+    #[test]
+    fn sphere_and_box_far_from_origin() {
+        let mut rng = Lcg(13);
+        let far = Float3 {x:300.0, y:200.0, z:100.0};
+        //never overlapping, the pair is settled by the nearest simplex search
+        for _ in 0..500 {
+            let a = Sphere {center:far + rng.point(1.0), radius:0.3 + rng.next() * 0.5};
+            let mut b = rng.oriented_box(1.0);
+            b.center = b.center + far + Float3 {x:5.0, y:0.0, z:0.0};
+            let r = check(&a, &b, &(a.center - b.center), &mut Frame3Simplex::new()).unwrap();
+            assert!(!r.is_colliding());
+            assert!(distance(&a, &b, &mut Frame3Simplex::new()).unwrap().distance > 0.0);
+        }
+    }
+
+    /// half the extent of `o_` along the unit vector `l_`
+    fn radius(o_:&OrientedBox, l_:&Float3) -> f32 {
+        o_.axes[0].dot(l_).abs() * o_.half_extents.x + o_.axes[1].dot(l_).abs() * o_.half_extents.y + o_.axes[2].dot(l_).abs() * o_.half_extents.z
+    }
+
+    /// penetration depth of two boxes along the axes of the separating axis test, negative when apart
+    fn box_depth(a_:&OrientedBox, b_:&OrientedBox) -> f32 {
+        let mut axis = a_.axes.to_vec();
+        axis.extend_from_slice(&b_.axes);
+        for p in a_.axes.iter() {
+            axis.extend(b_.axes.iter().map(|q| p.cross(q)));
+        }
+        axis.iter().filter(|l| l.magnitude() > 1e-6).map(|l| {
+            let mut l = *l;
+            l.normalize();
+            radius(a_, &l) + radius(b_, &l) - (b_.center - a_.center).dot(&l).abs()
+        }).fold(f32::MAX, f32::min)
+    }
+
+    #[test]
+    fn box_depth_matches_separating_axes() {
+        let mut rng = Lcg(23);
+        let mut count = 0;
+        while count < 1000 {
+            let (a, b) = (rng.oriented_box(1.5), rng.oriented_box(1.5));
+            let depth = box_depth(&a, &b);
+            if depth < 1e-2 { continue }
+            count += 1;
+            let r = check(&a, &b, &(a.center - b.center), &mut Frame3Simplex::new()).unwrap();
+            assert_eq!(r.status, CollisionStatus::Penetrating);
+            assert!((r.depth() - depth).abs() <= depth * 1e-3, "{} instead of {}", r.depth(), depth);
+            assert!((r.closest_a + r.normal * r.distance - r.closest_b).magnitude() <= 1e-3);
+        }
+    }
+
+    #[test]
+    fn distance_between_spheres() {
+        let mut rng = Lcg(29);
+        for _ in 0..1000 {
+            let a = Sphere {center:rng.point(4.0), radius:0.2 + rng.next()};
+            let b = Sphere {center:rng.point(4.0), radius:0.2 + rng.next()};
+            let gap = (b.center - a.center).magnitude() - a.radius - b.radius;
+            if gap < 1e-2 { continue }
+            let r = distance(&a, &b, &mut Frame3Simplex::new()).unwrap();
+            assert!((r.distance - gap).abs() <= 1e-4, "{} instead of {}", r.distance, gap);
+            assert!(((r.closest_a - a.center).magnitude() - a.radius).abs() <= 1e-4);
+            assert!((r.closest_a + r.normal * r.distance - r.closest_b).magnitude() <= 1e-4);
+        }
+    }
+
+    #[test]
+    fn distance_between_boxes() {
+        let mut rng = Lcg(31);
+        let mut count = 0;
+        while count < 1000 {
+            let (a, b) = (rng.oriented_box(4.0), rng.oriented_box(4.0));
+            let depth = box_depth(&a, &b);
+            if depth > -1e-2 { continue }
+            count += 1;
+            let r = distance(&a, &b, &mut Frame3Simplex::new()).unwrap();
+            //no axis separates the boxes by more than their distance, and the normal found does by exactly that
+            assert!(r.distance >= -depth - 1e-4);
+            let gap = (b.center - a.center).dot(&r.normal) - radius(&a, &r.normal) - radius(&b, &r.normal);
+            assert!((r.distance - gap).abs() <= 1e-4, "{} instead of {}", r.distance, gap);
+        }
+    }
+
+    #[test]
+    fn ray_cast_against_spheres() {
+        let mut rng = Lcg(37);
+        for _ in 0..2000 {
+            let sphere = Sphere {center:rng.point(4.0), radius:0.2 + rng.next()};
+            let origin = rng.point(4.0);
+            let dir = rng.point(8.0) - Float3 {x:4.0, y:4.0, z:4.0};
+            //roots of `|origin + dir * t - center|^2 = radius^2`, skipping rays grazing the sphere or ending on it
+            let m = origin - sphere.center;
+            let (a, b, c) = (dir.dot(&dir), m.dot(&dir), m.dot(&m) - sphere.radius * sphere.radius);
+            let disc = b * b - a * c;
+            let t = (-b - disc.max(0.0).sqrt()) / a;
+            if c.abs() < 1e-2 || disc.abs() < 1e-2 * a || (t - 1.0).abs() < 1e-3 { continue }
+            let hit = ray_cast(&sphere, &origin, &dir, &mut Frame3Simplex::new()).unwrap();
+            if c < 0.0 {
+                let hit = hit.unwrap();
+                assert_eq!(hit.fraction, 0.0);
+                assert!(hit.normal.is_zero());
+            } else if disc < 0.0 || !(0.0..=1.0).contains(&t) {
+                assert!(hit.is_none());
+            } else {
+                let hit = hit.unwrap();
+                assert!((hit.fraction - t).abs() <= 1e-4, "{} instead of {}", hit.fraction, t);
+                //the normal is that of the last plane the ray crossed, the hit point being on the surface up to the tolerance
+                let mut n = hit.point - sphere.center;
+                n.normalize();
+                assert!((hit.normal - n).magnitude() <= 1e-2);
+            }
+        }
+    }
+
+    #[test]
+    fn shape_cast_of_spheres() {
+        let mut rng = Lcg(41);
+        let half = Float3 {x:0.5, y:0.5, z:0.5};
+        let dt = 0.5;
+        for _ in 0..2000 {
+            let a = Sphere {center:rng.point(4.0), radius:0.2 + rng.next()};
+            let b = Sphere {center:rng.point(4.0), radius:0.2 + rng.next()};
+            let (va, vb) = ((rng.point(1.0) - half) * 8.0, (rng.point(1.0) - half) * 8.0);
+            //B seen from A is a ray against a sphere of both radii
+            let (m, dir) = (b.center - a.center, vb - va);
+            let radius = a.radius + b.radius;
+            let (p, q, c) = (dir.dot(&dir), m.dot(&dir), m.dot(&m) - radius * radius);
+            let disc = q * q - p * c;
+            let toi = (-q - disc.max(0.0).sqrt()) / p;
+            if c.abs() < 1e-2 || disc.abs() < 1e-2 * p || (toi - dt).abs() < 1e-3 { continue }
+            let hit = shape_cast(&a, &va, &b, &vb, dt, &mut Frame3Simplex::new()).unwrap();
+            if c < 0.0 {
+                assert_eq!(hit.unwrap().toi, 0.0);
+            } else if disc < 0.0 || !(0.0..=dt).contains(&toi) {
+                assert!(hit.is_none());
+            } else {
+                let hit = hit.unwrap();
+                assert!((hit.toi - toi).abs() <= 1e-4, "{} instead of {}", hit.toi, toi);
+                //both witness points are where the spheres meet at the time of impact
+                assert!((hit.closest_a - hit.closest_b).magnitude() <= 1e-3);
+                assert!(((hit.closest_a - a.center - va * hit.toi).magnitude() - a.radius).abs() <= 1e-3);
+            }
+        }
+    }
+
+    fn cast<S>(p_:Float3) -> Float3<S> where S: Scalar {
+        Float3 {x:S::from_f32(p_.x), y:S::from_f32(p_.y), z:S::from_f32(p_.z)}
+    }
+
+    /// random spheres and parallel capsules `gap_` apart, negative for an overlap, with and without a hint
+    fn resting<S>(gap_:S, tolerance_:S) where S: Scalar {
+        let mut rng = Lcg(19);
+        let half = Float3 {x:0.5, y:0.5, z:0.5};
+        for k in 0..1000 {
+            let mut dir = cast::<S>(rng.point(1.0) - half);
+            dir.normalize();
+            let (ra, rb) = (S::from_f32(0.2 + rng.next()), S::from_f32(0.2 + rng.next()));
+            let center = cast::<S>(rng.point(4.0));
+            let offset = dir * (ra + rb + gap_);
+            let ab = if k % 4 < 2 { Float3::zero() } else { -offset };
+            let r = if k % 2 == 0 {
+                let a = Sphere {center, radius:ra};
+                let b = Sphere {center:center + offset, radius:rb};
+                check(&a, &b, &ab, &mut Frame3Simplex::new())
+            } else {
+                let mut axis = cast::<S>(rng.point(1.0) - half).cross(&dir);
+                axis.normalize();
+                let a = Capsule {p0:center - axis, p1:center + axis, radius:ra};
+                let b = Capsule {p0:a.p0 + offset, p1:a.p1 + offset, radius:rb};
+                check(&a, &b, &ab, &mut Frame3Simplex::new())
+            };
+            let r = r.unwrap();
+            assert!((r.distance - gap_).abs() <= tolerance_, "{:?} instead of {:?}", r.distance, gap_);
+        }
+    }
+
+    #[test]
+    fn resting_shapes_f32() {
+        resting(0.0f32, 1e-3);
+        resting(-1e-4f32, 1e-3);
+    }
+
+    #[test]
+    fn resting_shapes_f64() {
+        resting(0.0f64, 1e-6);
+        resting(-1e-4f64, 1e-6);
+    }
+
+    #[test]
+    fn signed_distance_of_touching_spheres() {
+        let a = Sphere {center:Float3 {x:0.0, y:0.0, z:0.0}, radius:1.0};
+        let b = Sphere {center:Float3 {x:2.0, y:0.0, z:0.0}, radius:1.0};
+        let r = signed_distance(&a, &b, &Float3::zero(), &mut Frame3Simplex::new()).unwrap();
+        assert!(r.distance.abs() <= 1e-3);
+        let a = Sphere {center:Float3 {x:0.0f64, y:0.0, z:0.0}, radius:1.0};
+        let b = Sphere {center:Float3 {x:2.0, y:0.0, z:0.0}, radius:1.0};
+        let r = signed_distance(&a, &b, &Float3::zero(), &mut Frame3Simplex::new()).unwrap();
+        assert!(r.distance.abs() <= 1e-6);
+    }
+
+    #[test]
+    fn signed_distance_of_penetrating_spheres() {
+        let a = Sphere {center:Float3 {x:0.0, y:0.0, z:0.0}, radius:1.0};
+        let b = Sphere {center:Float3 {x:1.0, y:1.0, z:0.0}, radius:1.0};
+        let r = signed_distance(&a, &b, &(a.center - b.center), &mut Frame3Simplex::new()).unwrap();
+        let depth = 2.0 - 2.0f32.sqrt();
+        assert!(r.distance < 0.0);
+        //EPA approaches the spheres by flat faces, so the depth is only found up to its tolerance
+        assert!((r.distance + depth).abs() <= 1e-2, "{} instead of {}", r.distance, -depth);
+        let ab = (b.center - a.center) * (1.0 / 2.0f32.sqrt());
+        assert!((r.normal - ab).magnitude() <= 1e-2, "normal {}", r.normal);
+        assert!((r.closest_b - (r.closest_a + r.normal * r.distance)).magnitude() <= 1e-3);
+    }
+
+    #[test]
+    fn signed_distance_of_separated_shapes() {
+        let mut rng = Lcg(41);
+        for _ in 0..200 {
+            let a = rng.oriented_box(1.0);
+            let b = Sphere {center:rng.point(1.0) + Float3 {x:6.0, y:0.0, z:0.0}, radius:0.2 + rng.next()};
+            let r = signed_distance(&a, &b, &(a.center - b.center), &mut Frame3Simplex::new()).unwrap();
+            assert!(r.distance > 0.0);
+            assert!((r.normal.magnitude() - 1.0).abs() <= 1e-4);
+            assert!((r.closest_b - (r.closest_a + r.normal * r.distance)).magnitude() <= 1e-3);
+        }
+    }
+
+    #[test]
+    fn deep_spheres_not_converged() {
+        //EPA only approaches a sphere by ever smaller faces, so deep inside each other it runs out of iterations
+        let mut rng = Lcg(37);
+        let mut count = 0;
+        for _ in 0..200 {
+            let a = Sphere {center:rng.point(1.0), radius:1.0 + rng.next()};
+            let b = Sphere {center:rng.point(1.0), radius:1.0 + rng.next()};
+            let depth = a.radius + b.radius - (b.center - a.center).magnitude();
+            let r = check(&a, &b, &(a.center - b.center), &mut Frame3Simplex::new()).unwrap();
+            assert_eq!(r.status, CollisionStatus::Penetrating);
+            assert!(r.depth() <= depth + 1e-4);
+            if r.converged { assert!(depth - r.depth() <= depth * 1e-3, "{} instead of {}", r.depth(), depth); }
+            else { count += 1; }
+            //the nearest face found still bounds the depth from below
+            assert!(r.depth() >= depth * 0.9, "{} instead of {}", r.depth(), depth);
+        }
+        assert!(count > 0);
+    }
+
+    #[test]
+    fn coherent_matches_check() {
+        //boxes drifting past each other, apart at first then overlapping, a little further each frame
+        let mut rng = Lcg(41);
+        let (mut warm, mut cold) = (0, 0);
+        for _ in 0..50 {
+            let (a, mut b) = (rng.oriented_box(0.5), rng.oriented_box(0.5));
+            let step = (rng.point(1.0) - Float3 {x:0.5, y:0.5, z:0.5}) * 0.02;
+            b.center = a.center + Float3 {x:3.0, y:0.0, z:0.0};
+            let mut frame = CoherentFrame::new();
+            for _ in 0..100 {
+                b.center = b.center + step - Float3 {x:0.02, y:0.0, z:0.0};
+                let ab = a.center - b.center;
+                let r = check_coherent(&a, &b, &ab, &mut frame).unwrap();
+                let c = check(&a, &b, &ab, &mut Frame3Simplex::new()).unwrap();
+                assert_eq!(r.status, c.status);
+                assert!((r.distance - c.distance).abs() <= 1e-4, "{} instead of {}", r.distance, c.distance);
+                if c.status != CollisionStatus::Touching { assert!((r.normal - c.normal).magnitude() <= 1e-3); }
+                if !c.is_colliding() {
+                    warm += r.iteration;
+                    cold += c.iteration;
+                }
+            }
+        }
+        //apart, the last simplex usually still holds the nearest features
+        assert!(warm * 10 < cold, "{} iterations warm, {} cold", warm, cold);
+    }
+}
@@ -0,0 +1,629 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use crate::vector::{Float3, Scalar};
+use crate::support_map::{FeatureId, SupportMap, FACE_TOLERANCE};
+use crate::simplex_based_cd_helper::Error;
+
+// region ConvexHull
+
+/// Convex polytope with vertex adjacency, for hulls with too many vertices to scan on every support query.
+///
+/// Support queries walk the edges from the vertex the last query ended on towards the best one.
+/// On a convex polytope a vertex no neighbour improves on is the best one overall,
+/// and successive queries of a GJK run ask for nearby directions, so the walk only takes a few steps.
+/// Queries through a `Frame3Simplex` keep their start in the frame, so a `CoherentFrame` per pair
+/// keeps one start per shape and pair across frames; other queries share one start per shape.
+/// An empty hull reports the origin and is not valid.
+#[derive(Debug)]
+pub struct ConvexHull<S = f32> {
+    pub vertex: Vec<Float3<S>>,
+    /// indices of the vertices sharing an edge with each vertex
+    pub adjacency: Vec<Vec<usize>>,
+    /// vertex the last support query ended on
+    start: AtomicUsize,
+}
+
+impl<S> ConvexHull<S> where S: Scalar {
+    /// Builds the hull of `vertex_` from its faces, each a loop of vertex indices.
+    /// Vertices on no face are never reported by support queries.
+    pub fn new(vertex_:Vec<Float3<S>>, face_:&[Vec<usize>]) -> ConvexHull<S> {
+        let mut adjacency = vec![Vec::new(); vertex_.len()];
+        for f in face_ {
+            for (k, &i) in f.iter().enumerate() {
+                let j = f[(k + 1) % f.len()];
+                if i == j { continue }
+                adjacency[i].push(j);
+                adjacency[j].push(i);
+            }
+        }
+        for a in adjacency.iter_mut() {
+            a.sort_unstable();
+            a.dedup();
+        }
+        let start = adjacency.iter().position(|a| !a.is_empty()).unwrap_or(0);
+        ConvexHull {vertex:vertex_, adjacency, start:AtomicUsize::new(start)}
+    }
+
+    /// Index and support value of the vertex furthest along `d_`, walking from `start_`.
+    fn climb(&self, start_:usize, d_:&Float3<S>) -> (usize, S) {
+        let mut i = start_;
+        loop {
+            //compare against the current vertex, absolute values far from the origin round away small gains
+            let mut next = i;
+            let mut gain = S::ZERO;
+            for &j in &self.adjacency[i] {
+                let c = (self.vertex[j] - self.vertex[i]).dot(d_);
+                if c > gain {
+                    gain = c;
+                    next = j;
+                }
+            }
+            if next == i { break (i, self.vertex[i].dot(d_)) }
+            i = next;
+        }
+    }
+
+    /// Walks to the vertex furthest along `d_` from `start_`, or from the one the last query without a start ended on,
+    /// and leaves the vertex reached in `start_`, or for the next query without a start.
+    /// `None` for an empty hull.
+    fn climb_cached(&self, d_:&Float3<S>, start_:Option<&mut Option<FeatureId>>) -> Option<(usize, S)> {
+        if self.vertex.is_empty() || self.adjacency.len() != self.vertex.len() { return None }
+        //a start left by another shape is still a vertex to walk from
+        let last = self.vertex.len() - 1;
+        let ret = match start_ {
+            Some(start) => {
+                let ret = self.climb(start.unwrap_or_else(|| self.start.load(Ordering::Relaxed)).min(last), d_);
+                *start = Some(ret.0);
+                ret
+            },
+            None => {
+                let ret = self.climb(self.start.load(Ordering::Relaxed).min(last), d_);
+                self.start.store(ret.0, Ordering::Relaxed);
+                ret
+            },
+        };
+        Some(ret)
+    }
+
+    /// Collects the vertices connected to `from_` through vertices at or above `limit_` along `d_`.
+    fn flood(&self, from_:usize, d_:&Float3<S>, limit_:S, out_:&mut Vec<(Float3<S>, FeatureId)>) {
+        out_.clear();
+        out_.push((self.vertex[from_], from_));
+        let mut k = 0;
+        while k < out_.len() {
+            let i = out_[k].1;
+            for &j in &self.adjacency[i] {
+                if self.vertex[j].dot(d_) >= limit_ && !out_.iter().any(|p| p.1 == j) {
+                    out_.push((self.vertex[j], j));
+                }
+            }
+            k += 1;
+        }
+    }
+}
+
+impl<S> Clone for ConvexHull<S> where S: Scalar {
+    fn clone(&self) -> Self {
+        ConvexHull {
+            vertex: self.vertex.clone(),
+            adjacency: self.adjacency.clone(),
+            start: AtomicUsize::new(self.start.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl<S> SupportMap<Float3<S>> for ConvexHull<S> where S: Scalar {
+    fn support(&self, d_:&Float3<S>) -> (Float3<S>, FeatureId) {
+        match self.climb_cached(d_, None) {
+            Some((i, _)) => (self.vertex[i], i),
+            None => (Float3::zero(), 0),
+        }
+    }
+
+    /// every vertex tied for the furthest along `d_` lies on one face or edge, reachable through its edges
+    fn support_candidates(&self, d_:&Float3<S>, candidate_:&mut Vec<(Float3<S>, FeatureId)>) {
+        match self.climb_cached(d_, None) {
+            Some((i, max)) => self.flood(i, d_, max, candidate_),
+            None => { candidate_.clear(); candidate_.push((Float3::zero(), 0)); },
+        }
+    }
+
+    fn support_candidates_from(&self, d_:&Float3<S>, start_:&mut Option<FeatureId>, candidate_:&mut Vec<(Float3<S>, FeatureId)>) {
+        match self.climb_cached(d_, Some(start_)) {
+            Some((i, max)) => self.flood(i, d_, max, candidate_),
+            None => { candidate_.clear(); candidate_.push((Float3::zero(), 0)); },
+        }
+    }
+
+    fn support_face(&self, d_:&Float3<S>, face_:&mut Vec<(Float3<S>, FeatureId)>) {
+        let Some((i, max)) = self.climb_cached(d_, None) else {
+            face_.clear();
+            face_.push((Float3::zero(), 0));
+            return
+        };
+        //the extent along `d_` runs from minus the support value along `-d_` up to `max`
+        let (_, back) = self.climb(i, &-*d_);
+        let limit = max - (max + back) * S::from_f32(FACE_TOLERANCE);
+        self.flood(i, d_, limit, face_);
+    }
+
+    fn feature_point(&self, id_:FeatureId) -> Option<Float3<S>> { self.vertex.get(id_).copied() }
+
+    fn is_valid(&self) -> bool {
+        self.vertex.len() > 3 && self.adjacency.len() == self.vertex.len()
+    }
+}
+
+// endregion
+
+// region Polytope
+
+/// Directed edge of a `Polytope` face, paired with the edge running the other way on the neighbouring face.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HalfEdge {
+    /// vertex the edge starts from
+    pub origin: usize,
+    /// same edge on the neighbouring face
+    pub twin: usize,
+    /// following edge around `face`
+    pub next: usize,
+    pub face: usize,
+}
+
+/// Points `p` on the plane satisfy `normal.dot(p) == offset`, `normal` being unit length.
+#[derive(Debug, Clone, Copy)]
+pub struct Plane<S = f32> {
+    pub normal: Float3<S>,
+    pub offset: S,
+}
+
+impl<S> Plane<S> where S: Scalar {
+    /// signed distance of `p_` from the plane, positive on the side `normal` points to
+    pub fn distance(&self, p_:&Float3<S>) -> S {
+        self.normal.dot(p_) - self.offset
+    }
+}
+
+/// Convex polytope as a half-edge mesh, as built by `Polytope::quickhull`.
+#[derive(Debug, Clone)]
+pub struct Polytope<S = f32> {
+    pub vertex: Vec<Float3<S>>,
+    /// vertex loops, counter clockwise seen from outside
+    pub face: Vec<Vec<usize>>,
+    /// first half-edge of each face
+    pub face_edge: Vec<usize>,
+    pub edge: Vec<HalfEdge>,
+    /// outward plane of each face
+    pub plane: Vec<Plane<S>>,
+}
+
+impl<S> Polytope<S> where S: Scalar {
+    /// Convex hull of the point cloud `point_`, with coplanar triangles merged into polygonal faces.
+    /// Points inside the hull or on it within rounding are left out.
+    /// Fails with `Error::InvalidInput` when the points do not span a volume or one of them is not finite.
+    pub fn quickhull(point_:&[Float3<S>]) -> Result<Polytope<S>, Error> {
+        let finite = |v:S| v.to_f64().is_finite();
+        if !point_.iter().all(|p| finite(p.x) && finite(p.y) && finite(p.z)) { return Err(Error::InvalidInput) }
+        //tolerances follow the extent of the cloud rather than its distance from the origin
+        let (mut lo, mut hi) = (Float3::<S>::zero(), Float3::<S>::zero());
+        if let Some(p) = point_.first() { (lo, hi) = (*p, *p); }
+        for p in point_ {
+            lo = Float3 {x:lo.x.min(p.x), y:lo.y.min(p.y), z:lo.z.min(p.z)};
+            hi = Float3 {x:hi.x.max(p.x), y:hi.y.max(p.y), z:hi.z.max(p.z)};
+        }
+        let center = (lo + hi).scaled(S::ONE, S::from_f32(2.0));
+        let local: Vec<_> = point_.iter().map(|p| *p - center).collect();
+        let triangle = quickhull_triangles(&local)?;
+        Ok(Polytope::from_triangles(point_, &local, &triangle))
+    }
+
+    /// whether `p_` lies inside the polytope or within `tolerance_` of its boundary
+    pub fn contains(&self, p_:&Float3<S>, tolerance_:S) -> bool {
+        self.plane.iter().all(|plane| plane.distance(p_) <= tolerance_)
+    }
+
+    /// Merges neighbouring coplanar triangles into faces and builds the half-edge mesh of the result.
+    /// `triangle_` is built on `local_`, the points of `point_` moved next to the origin.
+    fn from_triangles(point_:&[Float3<S>], local_:&[Float3<S>], triangle_:&[Triangle<S>]) -> Polytope<S> {
+        let tolerance = hull_tolerance(local_);
+        let mut owner = HashMap::with_capacity(triangle_.len() * 3);
+        for (t, tri) in triangle_.iter().enumerate() {
+            for k in 0..3 {
+                owner.insert((tri.v[k], tri.v[(k + 1) % 3]), t);
+            }
+        }
+        //group triangles whose vertices all lie on each other's plane
+        let mut group: Vec<usize> = (0..triangle_.len()).collect();
+        fn root(group_:&mut [usize], mut i_:usize) -> usize {
+            while group_[i_] != i_ {
+                group_[i_] = group_[group_[i_]];
+                i_ = group_[i_];
+            }
+            i_
+        }
+        for (t, tri) in triangle_.iter().enumerate() {
+            for k in 0..3 {
+                let Some(&u) = owner.get(&(tri.v[(k + 1) % 3], tri.v[k])) else { continue };
+                let other = &triangle_[u];
+                let coplanar = other.v.iter().all(|i| tri.distance(&local_[*i]).abs() <= tolerance)
+                    && tri.v.iter().all(|i| other.distance(&local_[*i]).abs() <= tolerance);
+                if coplanar {
+                    let (a, b) = (root(&mut group, t), root(&mut group, u));
+                    group[a] = b;
+                }
+            }
+        }
+        //walk the boundary of each group, an edge is on it when its twin belongs to another group
+        let mut next_of: HashMap<usize, HashMap<usize, usize>> = HashMap::new();
+        for (t, tri) in triangle_.iter().enumerate() {
+            let g = root(&mut group, t);
+            for k in 0..3 {
+                let (a, b) = (tri.v[k], tri.v[(k + 1) % 3]);
+                let twin = owner.get(&(b, a)).map(|u| root(&mut group, *u));
+                if twin != Some(g) {
+                    next_of.entry(g).or_default().insert(a, b);
+                }
+            }
+        }
+        let mut roots: Vec<usize> = next_of.keys().copied().collect();
+        roots.sort_unstable();
+        let mut loops = Vec::with_capacity(roots.len());
+        for g in roots {
+            let boundary = &next_of[&g];
+            let start = *boundary.keys().min().unwrap_or(&0);
+            let mut face = Vec::with_capacity(boundary.len());
+            let mut i = start;
+            loop {
+                face.push(i);
+                i = boundary[&i];
+                if i == start || face.len() > boundary.len() { break }
+            }
+            loops.push(face);
+        }
+        //a vertex between only two faces lies on their common edge within tolerance and would stall hill climbing,
+        //leaving it out may collapse a face to an edge, which in turn can leave more such vertices
+        loop {
+            let mut incident: HashMap<usize, usize> = HashMap::new();
+            for i in loops.iter().flatten() {
+                *incident.entry(*i).or_default() += 1;
+            }
+            if incident.values().all(|n| *n > 2) { break }
+            for face in loops.iter_mut() {
+                face.retain(|i| incident[i] > 2);
+            }
+            loops.retain(|face| face.len() > 2);
+        }
+        let mut index = HashMap::new();
+        let mut ret = Polytope {vertex:Vec::new(), face:Vec::new(), face_edge:Vec::new(), edge:Vec::new(), plane:Vec::new()};
+        for face in loops {
+            let face = face.into_iter().map(|i| {
+                let n = ret.vertex.len();
+                *index.entry(i).or_insert_with(|| {
+                    ret.vertex.push(point_[i]);
+                    n
+                })
+            }).collect();
+            ret.face.push(face);
+        }
+        let mut edge_of = HashMap::with_capacity(ret.face.len() * 4);
+        for (f, face) in ret.face.iter().enumerate() {
+            let base = ret.edge.len();
+            ret.face_edge.push(base);
+            for (k, &v) in face.iter().enumerate() {
+                edge_of.insert((v, face[(k + 1) % face.len()]), base + k);
+                ret.edge.push(HalfEdge {origin:v, twin:0, next:base + (k + 1) % face.len(), face:f});
+            }
+            let mut center = Float3::zero();
+            for &v in face {
+                center = center + ret.vertex[v];
+            }
+            let center = center.scaled(S::ONE, S::from_f32(face.len() as f32));
+            //fan around the center, relative coordinates keep thin faces accurate
+            let mut normal = Float3::zero();
+            for (k, &v) in face.iter().enumerate() {
+                normal = normal + Float3::cross(&(ret.vertex[v] - center), &(ret.vertex[face[(k + 1) % face.len()]] - center));
+            }
+            let normal = normal.scaled(S::ONE, normal.magnitude());
+            ret.plane.push(Plane {normal, offset:normal.dot(&center)});
+        }
+        for e in 0..ret.edge.len() {
+            let (a, b) = (ret.edge[e].origin, ret.edge[ret.edge[e].next].origin);
+            ret.edge[e].twin = edge_of.get(&(b, a)).copied().unwrap_or(e);
+        }
+        ret
+    }
+}
+
+impl<S> ConvexHull<S> where S: Scalar {
+    /// Hull of the point cloud `point_`, see `Polytope::quickhull`.
+    pub fn from_points(point_:&[Float3<S>]) -> Result<ConvexHull<S>, Error> {
+        Ok(ConvexHull::from(&Polytope::quickhull(point_)?))
+    }
+}
+
+impl<S> From<&Polytope<S>> for ConvexHull<S> where S: Scalar {
+    fn from(p_:&Polytope<S>) -> Self {
+        ConvexHull::new(p_.vertex.clone(), &p_.face)
+    }
+}
+
+// endregion
+
+// region quickhull
+
+/// Hull triangle, wound counter clockwise seen from outside, with the points it still has to take in.
+#[derive(Debug, Clone)]
+struct Triangle<S> {
+    v: [usize; 3],
+    normal: Float3<S>,
+    offset: S,
+    outside: Vec<usize>,
+}
+
+impl<S> Triangle<S> where S: Scalar {
+    fn new(point_:&[Float3<S>], a_:usize, b_:usize, c_:usize) -> Triangle<S> {
+        //the cross product across the longest edge is the least affected by rounding on thin triangles
+        let (a, b, c) = (point_[a_], point_[b_], point_[c_]);
+        let (ab, bc, ca) = (b - a, c - b, a - c);
+        let (lab, lbc, lca) = (ab.sqr_magnitude(), bc.sqr_magnitude(), ca.sqr_magnitude());
+        let n = if lab >= lbc && lab >= lca { Float3::cross(&bc, &ca) }
+            else if lbc >= lca { Float3::cross(&ca, &ab) }
+            else { Float3::cross(&ab, &bc) };
+        let m = n.magnitude();
+        let normal = if m > S::ZERO { n.scaled(S::ONE, m) } else { n };
+        let center = (a + b + c).scaled(S::ONE, S::from_f32(3.0));
+        Triangle {v:[a_, b_, c_], normal, offset:normal.dot(&center), outside:Vec::new()}
+    }
+
+    fn distance(&self, p_:&Float3<S>) -> S {
+        self.normal.dot(p_) - self.offset
+    }
+}
+
+/// distance under which points count as lying on a plane, scaled to the coordinates of `point_`,
+/// which callers center on the origin so it follows the extent of the cloud
+fn hull_tolerance<S>(point_:&[Float3<S>]) -> S where S: Scalar {
+    let mut max = Float3::<S>::zero();
+    for p in point_ {
+        max = Float3 {x:max.x.max(p.x.abs()), y:max.y.max(p.y.abs()), z:max.z.max(p.z.abs())};
+    }
+    S::from_f32(3.0) * (max.x + max.y + max.z) * S::EPSILON
+}
+
+/// Quickhull: grows a tetrahedron by the point furthest outside one of its faces
+/// until every point is inside, returning the triangles of the hull.
+fn quickhull_triangles<S>(point_:&[Float3<S>]) -> Result<Vec<Triangle<S>>, Error> where S: Scalar {
+    if point_.len() < 4 { return Err(Error::InvalidInput) }
+    let tolerance = hull_tolerance(point_);
+    let furthest = |score:&dyn Fn(&Float3<S>) -> S| (0..point_.len())
+        .max_by(|a, b| score(&point_[*a]).partial_cmp(&score(&point_[*b])).unwrap_or(std::cmp::Ordering::Equal)).unwrap_or(0);
+    //initial tetrahedron from extreme points
+    let axis = [
+        Float3 {x:S::ONE, y:S::ZERO, z:S::ZERO},
+        Float3 {x:S::ZERO, y:S::ONE, z:S::ZERO},
+        Float3 {x:S::ZERO, y:S::ZERO, z:S::ONE},
+    ];
+    let (i0, i1) = axis.iter().map(|d| (furthest(&|p| -p.dot(d)), furthest(&|p| p.dot(d))))
+        .max_by(|a, b| (point_[a.1] - point_[a.0]).sqr_magnitude().partial_cmp(&(point_[b.1] - point_[b.0]).sqr_magnitude())
+            .unwrap_or(std::cmp::Ordering::Equal))
+        .unwrap_or((0, 1));
+    let line = point_[i1] - point_[i0];
+    if line.magnitude() <= tolerance { return Err(Error::InvalidInput) }
+    let i2 = furthest(&|p| Float3::cross(&line, &(*p - point_[i0])).sqr_magnitude());
+    let base = Triangle::new(point_, i0, i1, i2);
+    if base.normal.is_zero() || Float3::cross(&line, &(point_[i2] - point_[i0])).magnitude() <= tolerance * line.magnitude() {
+        return Err(Error::InvalidInput)
+    }
+    let i3 = furthest(&|p| base.distance(p).abs());
+    if base.distance(&point_[i3]).abs() <= tolerance { return Err(Error::InvalidInput) }
+    //wind every face so the opposite vertex is behind it
+    let (i1, i2) = if base.distance(&point_[i3]) > S::ZERO { (i2, i1) } else { (i1, i2) };
+    let mut triangle = vec![
+        Triangle::new(point_, i0, i1, i2),
+        Triangle::new(point_, i0, i3, i1),
+        Triangle::new(point_, i1, i3, i2),
+        Triangle::new(point_, i2, i3, i0),
+    ];
+    let mut alive = vec![true; 4];
+    let mut owner: HashMap<(usize, usize), usize> = HashMap::new();
+    for (t, tri) in triangle.iter().enumerate() {
+        for k in 0..3 {
+            owner.insert((tri.v[k], tri.v[(k + 1) % 3]), t);
+        }
+    }
+    for (i, p) in point_.iter().enumerate() {
+        if [i0, i1, i2, i3].contains(&i) { continue }
+        if let Some(t) = triangle.iter().position(|t| t.distance(p) > tolerance) {
+            triangle[t].outside.push(i);
+        }
+    }
+    let mut visible = Vec::new();
+    let mut horizon = Vec::new();
+    let mut orphan = Vec::new();
+    let mut t = 0;
+    while t < triangle.len() {
+        if !alive[t] || triangle[t].outside.is_empty() {
+            t += 1;
+            continue
+        }
+        let eye = *triangle[t].outside.iter()
+            .max_by(|a, b| triangle[t].distance(&point_[**a]).partial_cmp(&triangle[t].distance(&point_[**b])).unwrap_or(std::cmp::Ordering::Equal))
+            .unwrap_or(&triangle[t].outside[0]);
+        //flood the faces the eye point sees, the edges to faces it does not see form the horizon
+        visible.clear();
+        horizon.clear();
+        visible.push(t);
+        let mut k = 0;
+        while k < visible.len() {
+            let v = triangle[visible[k]].v;
+            for e in 0..3 {
+                let (a, b) = (v[e], v[(e + 1) % 3]);
+                let Some(&u) = owner.get(&(b, a)) else { continue };
+                if visible.contains(&u) { continue }
+                //also take in faces the new face on this edge would fold over, which rounding on thin faces can hide
+                let far = triangle[u].v.iter().copied().find(|i| *i != a && *i != b).unwrap_or(a);
+                let fold = Triangle::new(point_, a, b, eye).distance(&point_[far]) > tolerance;
+                if fold || triangle[u].distance(&point_[eye]) > tolerance { visible.push(u); }
+                else { horizon.push((a, b)); }
+            }
+            k += 1;
+        }
+        //a face seen later can turn a recorded horizon edge into an interior one
+        horizon.retain(|(a, b)| owner.get(&(*b, *a)).is_some_and(|u| !visible.contains(u)));
+        orphan.clear();
+        for &v in &visible {
+            alive[v] = false;
+            orphan.append(&mut triangle[v].outside);
+            let tri = triangle[v].v;
+            for e in 0..3 {
+                owner.remove(&(tri[e], tri[(e + 1) % 3]));
+                //a folded face may have had a vertex outside the new faces
+                if !horizon.iter().any(|(a, _)| *a == tri[e]) && !orphan.contains(&tri[e]) { orphan.push(tri[e]); }
+            }
+        }
+        let first = triangle.len();
+        for &(a, b) in &horizon {
+            let tri = Triangle::new(point_, a, b, eye);
+            for e in 0..3 {
+                owner.insert((tri.v[e], tri.v[(e + 1) % 3]), triangle.len());
+            }
+            triangle.push(tri);
+            alive.push(true);
+        }
+        for &i in &orphan {
+            if i == eye { continue }
+            if let Some(u) = (first..triangle.len()).find(|u| triangle[*u].distance(&point_[i]) > tolerance) {
+                triangle[u].outside.push(i);
+            }
+        }
+        t = 0;
+    }
+    Ok(triangle.into_iter().zip(alive).filter_map(|(t, a)| if a { Some(t) } else { None }).collect())
+}
+
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// deterministic numbers in [0, 1)
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> f32 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 40) as f32 / (1u64 << 24) as f32
+        }
+
+        /// direction in the unit cube around the origin
+        fn direction(&mut self) -> Float3 {
+            Float3 {x:self.next() - 0.5, y:self.next() - 0.5, z:self.next() - 0.5}
+        }
+
+        /// points around `center_`, most of them on a sphere so many lie close to each other's faces
+        fn cloud(&mut self, center_:Float3, count_:usize) -> Vec<Float3> {
+            (0..count_).map(|_| {
+                let mut d = self.direction();
+                if self.next() < 0.9 {
+                    d.normalize();
+                    d = d * 0.5;
+                }
+                center_ + d
+            }).collect()
+        }
+    }
+
+    /// clouds near the origin and far from it, and a grid with many coplanar points
+    fn clouds() -> Vec<Vec<Float3>> {
+        let mut rng = Lcg(3);
+        let mut ret = Vec::new();
+        for far in [0.0, 10.0, 1000.0, 5000.0] {
+            for _ in 0..10 {
+                let count = 20 + (rng.next() * 1000.0) as usize;
+                ret.push(rng.cloud(Float3 {x:far, y:far * 0.7, z:-far * 0.3}, count));
+            }
+        }
+        ret.push((0..125).map(|i| Float3 {x:(i % 5) as f32, y:((i / 5) % 5) as f32, z:(i / 25) as f32}).collect());
+        ret
+    }
+
+    #[test]
+    fn quickhull_euler_characteristic() {
+        for point in clouds() {
+            let p = Polytope::quickhull(&point).unwrap();
+            //every half-edge has a twin on another face
+            for (e, h) in p.edge.iter().enumerate() {
+                assert!(h.twin != e && p.edge[h.twin].twin == e && p.edge[h.twin].face != h.face);
+            }
+            assert_eq!(p.vertex.len() + p.face.len(), p.edge.len() / 2 + 2);
+        }
+    }
+
+    #[test]
+    fn non_finite_points() {
+        let mut rng = Lcg(7);
+        for bad in [f32::NAN, f32::INFINITY, f32::NEG_INFINITY] {
+            let mut point = rng.cloud(Float3::zero(), 50);
+            point[20].y = bad;
+            assert!(matches!(Polytope::quickhull(&point), Err(Error::InvalidInput)));
+            assert!(matches!(ConvexHull::from_points(&point), Err(Error::InvalidInput)));
+        }
+    }
+
+    #[test]
+    fn support_against_brute_force() {
+        let mut rng = Lcg(5);
+        for point in clouds() {
+            let hull = ConvexHull::from_points(&point).unwrap();
+            //relative to a point of the cloud, so far clouds compare without rounding of their position
+            let origin = point[0];
+            for _ in 0..200 {
+                let d = rng.direction();
+                let best = point.iter().map(|p| (*p - origin).dot(&d)).fold(f32::MIN, f32::max);
+                assert!(best - (hull.support(&d).0 - origin).dot(&d) <= 1e-5);
+            }
+        }
+    }
+
+    /// unit cube with its six faces
+    fn cube() -> ConvexHull {
+        let vertex = (0..8).map(|i| Float3 {x:(i & 1) as f32, y:((i >> 1) & 1) as f32, z:((i >> 2) & 1) as f32}).collect();
+        let face = vec![vec![0, 2, 3, 1], vec![4, 5, 7, 6], vec![0, 1, 5, 4], vec![2, 6, 7, 3], vec![0, 4, 6, 2], vec![1, 3, 7, 5]];
+        ConvexHull::new(vertex, &face)
+    }
+
+    #[test]
+    fn empty_hull() {
+        let hull = ConvexHull::<f32>::new(Vec::new(), &[]);
+        assert!(!hull.is_valid());
+        let d = Float3 {x:1.0, y:0.0, z:0.0};
+        assert_eq!(hull.support(&d).0, Float3::zero());
+        let mut start = Some(5);
+        let mut candidate = Vec::new();
+        hull.support_candidates_from(&d, &mut start, &mut candidate);
+        hull.support_face(&d, &mut candidate);
+        assert_eq!(candidate.len(), 1);
+    }
+
+    #[test]
+    fn start_per_frame() {
+        let hull = cube();
+        let (mut start_a, mut start_b) = (None, None);
+        let mut candidate = Vec::new();
+        let a = Float3 {x:1.0, y:1.0, z:1.0};
+        hull.support_candidates_from(&a, &mut start_a, &mut candidate);
+        assert_eq!(start_a, Some(7));
+        hull.support_candidates_from(&-a, &mut start_b, &mut candidate);
+        assert_eq!(start_b, Some(0));
+        //each query starts where its own last one ended, whatever the other asked
+        let d = Float3 {x:1.0, y:1.0, z:0.9};
+        hull.support_candidates_from(&d, &mut start_b, &mut candidate);
+        assert_eq!(candidate, vec![(Float3 {x:1.0, y:1.0, z:1.0}, 7)]);
+        assert_eq!((start_a, start_b), (Some(7), Some(7)));
+        //a start left by a larger shape is clamped to the vertices
+        let mut start = Some(100);
+        hull.support_candidates_from(&-a, &mut start, &mut candidate);
+        assert_eq!(start, Some(0));
+    }
+}
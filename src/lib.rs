@@ -0,0 +1,32 @@
+//! GJK/EPA convex collision detection.
+//!
+//! The crate root re-exports the types and queries that make up the public API,
+//! so callers can `use gjk_rust::{Float3, Frame3Simplex, check}` without reaching
+//! into the individual modules.
+
+pub mod vector;
+pub mod support_map;
+pub mod shape;
+pub mod hull;
+pub mod simplex_based_cd_helper;
+pub mod gjk3d;
+pub mod gjk2d;
+pub mod ccd;
+pub mod manifold;
+pub mod aabb;
+pub mod broadphase;
+pub mod world;
+pub mod obj;
+
+pub use vector::{Float2, Float3, Isometry, Mat3, Quat, Scalar, Vector};
+pub use support_map::{FeatureId, SupportMap, FACE_TOLERANCE};
+pub use shape::{Capsule, Cone, Cylinder, Ellipsoid, OrientedBox, Posed, Sphere};
+pub use hull::{ConvexHull, HalfEdge, Plane, Polytope};
+pub use simplex_based_cd_helper::{CoherentCache, CoherentFrame, CollisionResult, CollisionStatus, DistanceResult, Error, RayHit, ShapeCastHit, EPA2Simplex, Frame3Simplex, MinkowskiSumPoint, plane_normal, support};
+pub use gjk3d::{check, check_coherent, distance, ray_cast, shape_cast, signed_distance};
+pub use ccd::{ConservativeAdvancement, Motion};
+pub use manifold::{contact_manifold, ContactManifold, ContactPoint, ManifoldCache, PersistentManifold, PersistentPoint, NO_FEATURE};
+pub use aabb::Aabb;
+pub use broadphase::{Broadphase, BroadphaseKind, DynamicTree, ProxyId, SpatialHash, SweepAndPrune};
+pub use world::{Collision, Object, ObjectHandle, World};
+pub use obj::{parse_obj, read_obj, ObjError, ObjOptions, ObjShape};
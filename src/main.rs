@@ -1,29 +1,153 @@
-mod vector;
-mod simplex_based_cd_helper;
-mod gjk3d;
-use vector::Float3;
+use gjk_rust::{gjk3d, BroadphaseKind, CollisionResult, CollisionStatus, Float3, Frame3Simplex, Isometry, Motion, Object, Posed, World};
+use std::collections::BTreeSet;
 use std::fs::File;
 use std::io::{self, BufRead};
 
 struct ObjectInput {
+    /// name given in the input file, empty when unnamed
+    pub name: String,
+    /// vertices relative to `position`
     pub vertex: Vec<Float3>,
     pub position: Float3,
+    /// centroid of `vertex`, relative to `position`
     pub center: Float3,
     pub velocity: Float3,
 }
 
+impl ObjectInput {
+    fn pose(&self) -> Isometry {
+        Isometry::from_translation(self.position)
+    }
+
+    fn motion(&self) -> Motion {
+        Motion::new(self.pose(), self.velocity, Float3::zero())
+    }
+}
+
+/// Objects of an input file and the pairs to check among them.
+struct Scene {
+    object: Vec<ObjectInput>,
+    /// indices into `object`, smaller first and sorted, every pair is checked when empty
+    pair: Vec<(usize, usize)>,
+}
+
+impl Scene {
+    /// name of the object, or its index in the file when unnamed
+    fn label(&self, i_:usize) -> String {
+        match self.object[i_].name.as_str() {
+            "" => format!("#{}", i_),
+            name => name.to_string(),
+        }
+    }
+
+    fn is_checked(&self, pair_:(usize, usize)) -> bool {
+        self.pair.is_empty() || self.pair.binary_search(&pair_).is_ok()
+    }
+}
+
+/// command line: `gjk_rust [input] [dt] [duration] [broadphase]`
+struct Config {
+    input: String,
+    /// simulated seconds per step
+    dt: f32,
+    /// simulated seconds in total
+    duration: f32,
+    broadphase: BroadphaseKind,
+}
+
+impl Config {
+    fn from_args() -> Result<Config, String> {
+        let mut args = std::env::args().skip(1);
+        let input = args.next().unwrap_or_else(|| "input".to_string());
+        let dt = args.next().map_or(Ok(0.1), |s| s.parse::<f32>()).map_err(|e| format!("invalid dt: {}", e))?;
+        let duration = args.next().map_or(Ok(5.0), |s| s.parse::<f32>()).map_err(|e| format!("invalid duration: {}", e))?;
+        let broadphase = args.next().map_or(Ok(BroadphaseKind::SweepAndPrune), |s| s.parse::<BroadphaseKind>())?;
+        //nan never compares past the end time, the simulation would not stop
+        if !(dt.is_finite() && dt > 0.0) { return Err("dt must be positive and finite".to_string()) }
+        if !(duration.is_finite() && duration >= 0.0) { return Err("duration must be zero or more and finite".to_string()) }
+        Ok(Config {input, dt, duration, broadphase})
+    }
+}
+
 fn main() {
-    vector_test();
-    let mut frame = simplex_based_cd_helper::Frame3Simplex::<Float3>::new();
-    //TODO add main loop to animate the objects
-    match read_input("input") {
-        Ok((obj_a, obj_b)) => 
-            match gjk3d::check(&obj_a.vertex, &obj_b.vertex, &(obj_a.center - obj_b.center), &mut frame) {
-                Ok(true) => { println!("collide\n{:?}", frame); }
-                Ok(false) => { println!("miss\n{:?}", frame); }
-                Err(e) => { println!("error\n{:?}", e); }
-            },
-        Err(s) => println!("{:?}", s),
+    let config = match Config::from_args() {
+        Ok(c) => c,
+        Err(s) => { println!("{}", s); return }
+    };
+    match read_input(&config.input) {
+        Ok(scene) => simulate(&scene, &config),
+        Err(s) => println!("{}", s),
+    }
+}
+
+/// Moves the objects along their velocity and reports when the contact of each checked pair starts, continues and ends.
+fn simulate(scene_:&Scene, config_:&Config) {
+    let broadphase = match config_.broadphase.create() {
+        Ok(b) => b,
+        Err(e) => { println!("invalid broadphase {:?}", e); return },
+    };
+    let mut world = World::new(broadphase);
+    //slots of a fresh world follow the order of addition, so handle indices are object indices
+    let handle:Vec<_> = scene_.object.iter().map(|o| world.add(Object::new(o.vertex.clone(), o.pose()))).collect();
+    let motion:Vec<_> = scene_.object.iter().map(|o| o.motion()).collect();
+    let mut in_contact = BTreeSet::new();
+    let mut step = 0;
+    loop {
+        //derive the time from the step count so rounding does not pile up
+        let t = step as f32 * config_.dt;
+        if t > config_.duration { break }
+        step += 1;
+        for (h, m) in handle.iter().zip(&motion) { world.set_pose(*h, m.pose_at(t)); }
+        let collision = world.detect();
+        let mut touching = BTreeSet::new();
+        for (a, b, e) in world.failures() {
+            let pair = (a.index(), b.index());
+            if !scene_.is_checked(pair) { continue }
+            println!("t={:.3} {} error {:?}", t, pair_label(scene_, pair), e);
+            //nothing is known about the pair this step, keep its contact going
+            if in_contact.contains(&pair) { touching.insert(pair); }
+        }
+        for c in &collision {
+            let pair = (c.a.index(), c.b.index());
+            if !scene_.is_checked(pair) { continue }
+            let event = if in_contact.contains(&pair) { "contact" } else { "contact start" };
+            print_event(t, &pair_label(scene_, pair), event, &c.result);
+            touching.insert(pair);
+        }
+        //the world only reports colliding pairs, query the separated ones again to show their gap
+        for &pair in in_contact.difference(&touching) {
+            let (obj_a, obj_b) = (&scene_.object[pair.0], &scene_.object[pair.1]);
+            let a = Posed::new(obj_a.vertex.as_slice(), motion[pair.0].pose_at(t));
+            let b = Posed::new(obj_b.vertex.as_slice(), motion[pair.1].pose_at(t));
+            let ab = a.pose().transform_point(&obj_a.center) - b.pose().transform_point(&obj_b.center);
+            match gjk3d::check(&a, &b, &ab, &mut Frame3Simplex::new()) {
+                Ok(r) => print_event(t, &pair_label(scene_, pair), "contact end", &r),
+                Err(e) => println!("t={:.3} {} error {:?}", t, pair_label(scene_, pair), e),
+            }
+        }
+        in_contact = touching;
+    }
+}
+
+fn pair_label(scene_:&Scene, pair_:(usize, usize)) -> String {
+    format!("{}-{}", scene_.label(pair_.0), scene_.label(pair_.1))
+}
+
+fn print_event(t_:f32, pair_:&str, event_:&str, r_:&CollisionResult<Float3>) {
+    //moving B by `mtv` separates the objects, when apart the normal and gap are shown instead
+    match r_.status {
+        CollisionStatus::Disjoint => println!(
+            "t={:.3} {} {}: normal {} distance {} point a {} point b {}",
+            t_, pair_, event_, r_.normal, r_.distance, r_.closest_a, r_.closest_b
+        ),
+        _ => {
+            //scaling the normal by a zero depth gives -0 components
+            let mtv = if r_.depth() > 0.0 { r_.normal * r_.depth() } else { Float3::zero() };
+            println!(
+                "t={:.3} {} {} ({:?}): mtv {} depth {} point a {} point b {}",
+                t_, pair_, event_, r_.status, mtv, r_.depth(), r_.closest_a, r_.closest_b
+            )
+        },
     }
 }
 
@@ -31,67 +155,113 @@ fn main() {
 enum ParseError {
     IO(io::Error),
     Length,
-    Non3Multiple,
+    /// line number of the object
+    Non3Multiple(usize),
+    /// line number of the object and the field that is left out
+    MissingField(usize, &'static str),
+    /// line number of the object and the field that does not have 3 components
+    Not3Components(usize, &'static str),
+    /// line number and the token that is not a number
+    InvalidNumber(usize, String),
+    DuplicateName(usize, String),
+    /// line number of the pair and the name or index that matches no object
+    UnknownObject(usize, String),
+    /// line number of a pair that does not name two objects
+    InvalidPair(usize),
 }
 
-fn vector_test() {
-    let a = Float3 {x:1.0, y:1.0, z:1.0};
-    let b = Float3 {x:2.0, y:3.0, z:4.0};
-    let a_copy = Float3 {x:1.0, y:1.0, z:1.0};
-    assert_ne!(a, b);
-    assert_eq!(a, a_copy);
-    assert_eq!(a.sqr_magnitude(), 3.0);
-    let c = a + b;
-    assert!(c.x == 3.0 && c.y == 4.0 && c.z == 5.0);
-    let r = a.dot(&b);
-    println!("a dot b = {}", r);
-    println!("a cross b = {}", Float3::cross(&a, &b));
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ParseError::IO(e) => write!(f, "io error: {}", e),
+            ParseError::Length => write!(f, "expected at least two objects"),
+            ParseError::Non3Multiple(l) => write!(f, "line {}: vertex components are not a multiple of 3", l),
+            ParseError::MissingField(l, n) => write!(f, "line {}: {} is missing", l, n),
+            ParseError::Not3Components(l, n) => write!(f, "line {}: {} needs 3 components", l, n),
+            ParseError::InvalidNumber(l, t) => write!(f, "line {}: '{}' is not a number", l, t),
+            ParseError::DuplicateName(l, n) => write!(f, "line {}: object {} is already defined", l, n),
+            ParseError::UnknownObject(l, n) => write!(f, "line {}: no object named {}", l, n),
+            ParseError::InvalidPair(l) => write!(f, "line {}: a pair needs two different objects", l),
+        }
+    }
 }
 
-fn read_input(path:&str) -> Result<(ObjectInput, ObjectInput), ParseError> {
+/// Reads one object per line as `[name:] position|velocity|vertices`, and `pair <a> <b>` lines
+/// naming objects by name or index to check only those pairs.
+fn read_input(path:&str) -> Result<Scene, ParseError> {
     let file = File::open(path).map_err(ParseError::IO)?;
-    let mut iter = io::BufReader::new(file).lines().filter_map(|line| line.ok())
-        .filter_map(|line| if line.starts_with("#") { Option::None } else { Option::Some(line) })
-        .map(|line| parse_from(&line));
-    let a = iter.next().ok_or(ParseError::Length)?.ok_or(ParseError::Non3Multiple)?;
-    let b = iter.next().ok_or(ParseError::Length)?.ok_or(ParseError::Non3Multiple)?;
-    Ok((a, b))
+    let mut object:Vec<ObjectInput> = Vec::new();
+    //pairs are resolved once every object is known, so they may come before the objects they name
+    let mut pair_line:Vec<(usize, String)> = Vec::new();
+    for (i, line) in io::BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(ParseError::IO)?;
+        let number = i + 1;
+        if line.starts_with("#") || line.trim().is_empty() { continue }
+        if line.split_whitespace().next() == Some("pair") {
+            pair_line.push((number, line));
+            continue
+        }
+        let (name, rest) = match line.split_once(':') {
+            Some((name, rest)) => (name.trim(), rest),
+            None => ("", line.as_str()),
+        };
+        if !name.is_empty() && object.iter().any(|o| o.name == name) {
+            return Err(ParseError::DuplicateName(number, name.to_string()))
+        }
+        let mut obj = parse_from(number, rest)?;
+        obj.name = name.to_string();
+        object.push(obj);
+    }
+    if object.len() < 2 { return Err(ParseError::Length) }
+    let mut pair = Vec::new();
+    for (number, line) in &pair_line {
+        let mut iter = line.split_whitespace().skip(1).map(|s| find_object(&object, s).ok_or_else(|| ParseError::UnknownObject(*number, s.to_string())));
+        let (a, b) = match (iter.next(), iter.next(), iter.next()) {
+            (Some(a), Some(b), None) => (a?, b?),
+            _ => return Err(ParseError::InvalidPair(*number)),
+        };
+        if a == b { return Err(ParseError::InvalidPair(*number)) }
+        pair.push((a.min(b), a.max(b)));
+    }
+    pair.sort();
+    pair.dedup();
+    Ok(Scene {object, pair})
+}
+
+/// index of the object named `s_`, or else of the object at index `s_`
+fn find_object(object_:&[ObjectInput], s_:&str) -> Option<usize> {
+    object_.iter().position(|o| o.name == s_)
+        .or_else(|| s_.parse::<usize>().ok().filter(|&i| i < object_.len()))
 }
 
-fn parse_from(line:&str) -> Option<ObjectInput> {
-    let mut iter = line.split(|c| c == ',' || c == '|' || c == ' ').filter_map(|s| s.parse::<f32>().ok());
-    let mut ret:Vec<Float3> = Vec::new();
+/// Parses `position|velocity|vertices` of the object on line `number_`.
+fn parse_from(number_:usize, line_:&str) -> Result<ObjectInput, ParseError> {
+    //the vertices take the rest of the line
+    let mut field = line_.splitn(3, '|');
+    let mut next = |name_:&'static str| -> Result<Vec<f32>, ParseError> {
+        let ret = field.next().unwrap_or("").split([',', ' ']).filter(|s| !s.trim().is_empty())
+            .map(|s| s.trim().parse::<f32>().ok().filter(|v| v.is_finite()).ok_or_else(|| ParseError::InvalidNumber(number_, s.trim().to_string())))
+            .collect::<Result<Vec<_>, _>>()?;
+        if ret.is_empty() { return Err(ParseError::MissingField(number_, name_)) }
+        Ok(ret)
+    };
+    let float3 = |c_:&[f32]| Float3 {x:c_[0], y:c_[1], z:c_[2]};
     //root/origin position
-    let pos_x = iter.next()?;
-    let pos_y = iter.next()?;
-    let pos_z = iter.next()?;
+    let position = next("position")?;
+    if position.len() != 3 { return Err(ParseError::Not3Components(number_, "position")) }
     //move velocity
-    let vel_x = iter.next()?;
-    let vel_y = iter.next()?;
-    let vel_z = iter.next()?;
-    let mut center_x = 0.0;
-    let mut center_y = 0.0;
-    let mut center_z = 0.0;
-    //x component of the first input vertex, at least one point is required
-    let mut x = iter.next()?;
-    loop {
-        let y = iter.next()?;
-        let z = iter.next()?;
-        center_x += x;
-        center_y += y;
-        center_z += z;
-        ret.push(Float3{x:x + pos_x, y:y + pos_y, z:z + pos_z});
-        //next x
-        match iter.next() {
-            Some(v) => x = v,
-            None => break,
-        }
-    }
-    let len = ret.len() as f32;
-    Some(ObjectInput {
-        vertex: ret,
-        position: Float3{x:pos_x, y:pos_y, z:pos_z},
-        center: Float3{x:center_x/len + pos_x, y:center_y/len + pos_y, z:center_z/len + pos_z}, 
-        velocity: Float3{x:vel_x, y:vel_y, z:vel_z}
+    let velocity = next("velocity")?;
+    if velocity.len() != 3 { return Err(ParseError::Not3Components(number_, "velocity")) }
+    //at least one point is required
+    let component = next("vertices")?;
+    if component.len() % 3 != 0 { return Err(ParseError::Non3Multiple(number_)) }
+    let vertex:Vec<Float3> = component.chunks(3).map(float3).collect();
+    let center = vertex.iter().fold(Float3::zero(), |c, v| c + *v) * (1.0 / vertex.len() as f32);
+    Ok(ObjectInput {
+        name: String::new(),
+        vertex,
+        position: float3(&position),
+        center,
+        velocity: float3(&velocity),
     })
-}
\ No newline at end of file
+}
@@ -0,0 +1,509 @@
+use std::collections::HashMap;
+use crate::vector::{Float3, Isometry, Scalar};
+use crate::simplex_based_cd_helper::{CollisionResult, Frame3Simplex};
+use crate::support_map::{FeatureId, SupportMap};
+
+const MAX_CONTACT: usize = 4;
+/// stands for the inside of the reference face in `ContactPoint::feature`
+pub const NO_FEATURE: FeatureId = FeatureId::MAX;
+/// face vertex and its feature
+type FacePoint<S> = (Float3<S>, FeatureId);
+/// clipped point with its incident and reference features
+type ClipPoint<S> = (Float3<S>, FeatureId, FeatureId);
+
+// region ContactManifold
+
+#[derive(Debug, Clone)]
+pub struct ContactPoint<S = f32> {
+    /// contact point on A
+    pub point_a: Float3<S>,
+    /// contact point on B
+    pub point_b: Float3<S>,
+    /// penetration depth along the manifold normal, negative while the points are still apart
+    pub depth: S,
+    /// feature of A the point was built from, see `ContactPoint::feature`
+    pub feature_a: FeatureId,
+    /// feature of B the point was built from, see `ContactPoint::feature`
+    pub feature_b: FeatureId,
+}
+
+impl<S> ContactPoint<S> {
+    /// Features the point was built from, in the numbering of `SupportMap` and `MinkowskiSumPoint.a`/`.b`:
+    /// the incident vertex and `NO_FEATURE` for a vertex lying on the reference face,
+    /// the start vertices of the incident edge and the reference edge for a point cut by the reference face sides.
+    /// Stays the same from frame to frame while the shapes keep touching the same way.
+    pub fn feature(&self) -> (FeatureId, FeatureId) {
+        (self.feature_a, self.feature_b)
+    }
+}
+
+/// Contact points shared by two overlapping shapes, all with the same normal.
+#[derive(Debug, Clone)]
+pub struct ContactManifold<S = f32> {
+    /// unit vector from A towards B
+    pub normal: Float3<S>,
+    /// at most `MAX_CONTACT` points, empty when the shapes do not touch
+    pub points: Vec<ContactPoint<S>>,
+}
+
+// endregion
+
+/// Builds the contact manifold of `polya_` and `polyb_` from the result of `check`.
+///
+/// The faces of both shapes most aligned with the collision normal are found with `support_face`;
+/// the one facing the normal more squarely becomes the reference face, the other one is clipped against its sides.
+/// Clipped points behind the reference face are the contacts, reduced to the four spanning the largest area.
+/// Shapes without a face along the normal fall back to the single pair of witness points.
+pub fn contact_manifold<S, A, B>(
+    polya_:&A, polyb_:&B, r_:&CollisionResult<Float3<S>>, f_:&mut Frame3Simplex<Float3<S>>
+) -> ContactManifold<S>
+    where S: Scalar, A: SupportMap<Float3<S>> + ?Sized, B: SupportMap<Float3<S>> + ?Sized
+{
+    let n = r_.normal;
+    let mut ret = ContactManifold {normal:n, points:Vec::with_capacity(MAX_CONTACT)};
+    if !r_.is_colliding() { return ret }
+    let witness = witness(r_);
+    if n.is_zero() {
+        ret.points.push(witness);
+        return ret
+    }
+    polya_.support_face(&n, &mut f_.candidate_a);
+    polyb_.support_face(&-n, &mut f_.candidate_b);
+    let face_a = polygon(&f_.candidate_a, &n);
+    let face_b = polygon(&f_.candidate_b, &-n);
+    let alignment_a = face_normal(&face_a).map(|fa| fa.dot(&n).abs());
+    let alignment_b = face_normal(&face_b).map(|fb| fb.dot(&n).abs());
+    //the reference face belongs to A unless B has a face squarer to the normal
+    let a_is_reference = match (alignment_a, alignment_b) {
+        (Some(a), Some(b)) => a >= b,
+        (Some(_), None) => true,
+        (None, Some(_)) => false,
+        (None, None) => {
+            ret.points.push(witness);
+            return ret
+        },
+    };
+    let (reference, incident, out) = if a_is_reference { (&face_a, &face_b, n) } else { (&face_b, &face_a, -n) };
+    let mut rn = face_normal(reference).unwrap_or(out);
+    if rn.dot(&out) < S::ZERO { rn = -rn; }
+    let offset = rn.dot(&reference[0].0);
+    let clipped = clip(incident, reference, &rn);
+    let scale = reference.iter().chain(incident.iter()).map(|p| (p.0 - reference[0].0).magnitude()).fold(S::ZERO, |a, b| a.max(b));
+    let mut contact: Vec<ContactPoint<S>> = Vec::with_capacity(clipped.len());
+    for (p, incident_id, reference_id) in clipped {
+        let s = rn.dot(&p) - offset;
+        if s > scale * S::TOLERANCE { continue }
+        let on_reference = p - rn * s;
+        let (point_a, point_b, feature_a, feature_b) = if a_is_reference {
+            (on_reference, p, reference_id, incident_id)
+        } else { (p, on_reference, incident_id, reference_id) };
+        //depth is measured along the manifold normal, whichever face is the reference
+        contact.push(ContactPoint {point_a, point_b, depth: (point_a - point_b).dot(&n), feature_a, feature_b});
+    }
+    if contact.is_empty() {
+        ret.points.push(witness);
+        return ret
+    }
+    reduce(&mut contact, &n, |p| (p.point_b, p.depth));
+    ret.points = contact;
+    ret
+}
+
+/// single contact from the witness points of `r_`, tagged with the features GJK ended on
+fn witness<S>(r_:&CollisionResult<Float3<S>>) -> ContactPoint<S> where S: Scalar {
+    let (feature_a, feature_b) = r_.simplex.first().map_or((NO_FEATURE, NO_FEATURE), |p| (p.a, p.b));
+    ContactPoint {point_a:r_.closest_a, point_b:r_.closest_b, depth:r_.depth(), feature_a, feature_b}
+}
+
+/// Orders the points of a face as a convex polygon, counter clockwise seen from `n_`.
+/// Points inside the polygon or on its edges are dropped.
+fn polygon<S>(face_:&[FacePoint<S>], n_:&Float3<S>) -> Vec<FacePoint<S>> where S: Scalar {
+    let (u, v) = tangent_basis(n_);
+    let mut p: Vec<(S, S, FacePoint<S>)> = face_.iter().map(|p| (p.0.dot(&u), p.0.dot(&v), *p)).collect();
+    p.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal)
+        .then(a.1.partial_cmp(&b.1).unwrap_or(std::cmp::Ordering::Equal)));
+    if p.len() < 3 {
+        p.dedup_by(|a, b| a.2.0 == b.2.0);
+        return p.into_iter().map(|p| p.2).collect()
+    }
+    //monotone chain, lower hull then upper hull
+    let turn = |o:&(S, S, _), a:&(S, S, _), b:&(S, S, _)| (a.0 - o.0) * (b.1 - o.1) - (a.1 - o.1) * (b.0 - o.0);
+    let mut hull: Vec<(S, S, FacePoint<S>)> = Vec::with_capacity(p.len() * 2);
+    for pass in 0..2 {
+        let start = hull.len();
+        for k in 0..p.len() {
+            let q = &p[if pass == 0 { k } else { p.len() - 1 - k }];
+            while hull.len() >= start + 2 && turn(&hull[hull.len() - 2], &hull[hull.len() - 1], q) <= S::ZERO {
+                hull.pop();
+            }
+            hull.push(*q);
+        }
+        //the last point of a chain starts the next one
+        hull.pop();
+    }
+    hull.into_iter().map(|p| p.2).collect()
+}
+
+/// unit vectors `u`, `v` such that `u`, `v`, `n_` is right handed
+fn tangent_basis<S>(n_:&Float3<S>) -> (Float3<S>, Float3<S>) where S: Scalar {
+    let (zero, one) = (S::ZERO, S::ONE);
+    let axis = if n_.x.abs() < S::from_f32(0.57) { Float3 {x:one, y:zero, z:zero} } else { Float3 {x:zero, y:one, z:zero} };
+    let u = Float3::cross(&axis, n_);
+    let u = u.scaled(one, u.magnitude());
+    let v = Float3::cross(n_, &u);
+    (u, v.scaled(one, v.magnitude()))
+}
+
+/// unit normal of a counter clockwise polygon, `None` for points and segments
+fn face_normal<S>(p_:&[FacePoint<S>]) -> Option<Float3<S>> where S: Scalar {
+    if p_.len() < 3 { return None }
+    let mut n = Float3::zero();
+    for (i, p) in p_.iter().enumerate() {
+        n = n + Float3::cross(&p.0, &p_[(i + 1) % p_.len()].0);
+    }
+    let m = n.magnitude();
+    if m > S::ZERO { Some(n.scaled(S::ONE, m)) } else { None }
+}
+
+/// Clips the `incident_` polygon, segment or point against the side planes of the convex `reference_` polygon.
+/// Each point keeps the incident feature it lies on and the reference edge that cut it, see `ContactPoint::feature`.
+fn clip<S>(
+    incident_:&[FacePoint<S>], reference_:&[FacePoint<S>], rn_:&Float3<S>
+) -> Vec<ClipPoint<S>> where S: Scalar {
+    let mut ret: Vec<ClipPoint<S>> = incident_.iter().map(|p| (p.0, p.1, NO_FEATURE)).collect();
+    let mut next = Vec::with_capacity(ret.len() * 2);
+    let count = reference_.len();
+    //the winding of the reference face decides which side of each edge is inside
+    let side = if face_normal(reference_).is_some_and(|fr| fr.dot(rn_) < S::ZERO) { -S::ONE } else { S::ONE };
+    for i in 0..count {
+        let (e0, e1) = (reference_[i], reference_[(i + 1) % count]);
+        let plane = Float3::cross(&(e1.0 - e0.0), rn_) * side;
+        let distance = |p:&ClipPoint<S>| plane.dot(&(p.0 - e0.0));
+        let cut = |a:&ClipPoint<S>, b:&ClipPoint<S>, da:S, db:S|
+            (a.0 + (b.0 - a.0) * (da / (da - db)), a.1, e0.1);
+        next.clear();
+        match ret.len() {
+            1 => if distance(&ret[0]) <= S::ZERO { next.push(ret[0]); },
+            2 => {
+                //a segment is not closed, walking it back would duplicate the clipped points
+                let (a, b) = (ret[0], ret[1]);
+                let (da, db) = (distance(&a), distance(&b));
+                if da <= S::ZERO || db <= S::ZERO {
+                    next.push(if da <= S::ZERO { a } else { cut(&a, &b, da, db) });
+                    next.push(if db <= S::ZERO { b } else { cut(&a, &b, da, db) });
+                }
+            },
+            _ => for j in 0..ret.len() {
+                let (a, b) = (ret[j], ret[(j + 1) % ret.len()]);
+                let (da, db) = (distance(&a), distance(&b));
+                if da <= S::ZERO { next.push(a); }
+                if (da <= S::ZERO) != (db <= S::ZERO) { next.push(cut(&a, &b, da, db)); }
+            },
+        }
+        std::mem::swap(&mut ret, &mut next);
+        if ret.is_empty() { break }
+    }
+    ret
+}
+
+/// Keeps at most `MAX_CONTACT` of the points `at_` places with their depth: the deepest, the one furthest from it,
+/// the one spanning the largest triangle with both and the one adding the most area to that triangle.
+fn reduce<S, T>(p_:&mut Vec<T>, n_:&Float3<S>, at_:impl Fn(&T) -> (Float3<S>, S)) where S: Scalar {
+    if p_.len() <= MAX_CONTACT { return }
+    let at = |i:usize| at_(&p_[i]).0;
+    let best = |score:&dyn Fn(usize) -> S| (0..p_.len()).max_by(|a, b| score(*a).partial_cmp(&score(*b)).unwrap_or(std::cmp::Ordering::Equal)).unwrap_or(0);
+    let area = |a:usize, b:usize, c:usize| Float3::cross(&(at(b) - at(a)), &(at(c) - at(a))).dot(n_);
+    let i0 = best(&|i| at_(&p_[i]).1);
+    let i1 = best(&|i| (at(i) - at(i0)).sqr_magnitude());
+    let i2 = best(&|i| area(i0, i1, i).abs());
+    //the fourth point lies outside one of the triangle edges, on the side opposite to the triangle winding
+    let winding = if area(i0, i1, i2) < S::ZERO { -S::ONE } else { S::ONE };
+    let i3 = best(&|i| (-area(i0, i1, i) * winding).max(-area(i1, i2, i) * winding).max(-area(i2, i0, i) * winding));
+    let keep = [i0, i1, i2, i3];
+    let mut i = 0;
+    p_.retain(|_| { i += 1; keep.contains(&(i - 1)) });
+}
+
+// region ManifoldCache
+
+/// A contact point kept across frames, with the impulses a solver applied to it.
+#[derive(Debug, Clone)]
+pub struct PersistentPoint<S = f32> {
+    pub contact: ContactPoint<S>,
+    /// `contact.point_a` in the local space of A
+    pub local_a: Float3<S>,
+    /// `contact.point_b` in the local space of B
+    pub local_b: Float3<S>,
+    /// impulse along the normal, for the solver to warm start from
+    pub normal_impulse: S,
+    /// friction impulse in the contact plane, for the solver to warm start from
+    pub tangent_impulse: Float3<S>,
+    /// frames the point has been matched for
+    pub age: usize,
+}
+
+#[derive(Debug, Clone)]
+pub struct PersistentManifold<S = f32> {
+    /// unit vector from A towards B
+    pub normal: Float3<S>,
+    /// at most `MAX_CONTACT` points
+    pub points: Vec<PersistentPoint<S>>,
+}
+
+/// Contact manifolds kept from frame to frame, keyed by a pair of object ids chosen by the caller.
+///
+/// Every frame `update` matches the new contacts of a pair to its cached ones, first by `ContactPoint::feature`
+/// then by distance, so matched points keep their impulses.
+/// Cached points the new manifold does not report follow their objects and are kept until they drift apart.
+#[derive(Debug, Clone)]
+pub struct ManifoldCache<S = f32> {
+    /// how far the two sides of a point may drift apart, sideways or out of contact, before it is dropped
+    pub breaking_threshold: S,
+    manifolds: HashMap<(usize, usize), PersistentManifold<S>>,
+}
+
+impl<S> ManifoldCache<S> where S: Scalar {
+    pub fn new(breaking_threshold_:S) -> ManifoldCache<S> {
+        ManifoldCache {breaking_threshold:breaking_threshold_, manifolds:HashMap::new()}
+    }
+
+    pub fn get(&self, pair_:(usize, usize)) -> Option<&PersistentManifold<S>> {
+        self.manifolds.get(&pair_)
+    }
+
+    /// for the solver to store the impulses it applied
+    pub fn get_mut(&mut self, pair_:(usize, usize)) -> Option<&mut PersistentManifold<S>> {
+        self.manifolds.get_mut(&pair_)
+    }
+
+    pub fn remove(&mut self, pair_:(usize, usize)) -> Option<PersistentManifold<S>> {
+        self.manifolds.remove(&pair_)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (&(usize, usize), &PersistentManifold<S>)> {
+        self.manifolds.iter()
+    }
+
+    /// Merges this frame's manifold `m_` of `pair_` into the cached one, `pose_a_` and `pose_b_` being where A and B are now.
+    /// An empty manifold forgets the pair.
+    pub fn update(
+        &mut self, pair_:(usize, usize), m_:&ContactManifold<S>, pose_a_:&Isometry<S>, pose_b_:&Isometry<S>
+    ) -> Option<&PersistentManifold<S>> {
+        if m_.points.is_empty() {
+            self.manifolds.remove(&pair_);
+            return None
+        }
+        let n = m_.normal;
+        let threshold = self.breaking_threshold;
+        let mut old: Vec<PersistentPoint<S>> = self.manifolds.remove(&pair_).map(|m| m.points).unwrap_or_default();
+        //move the cached points along with their objects, dropping the ones whose sides drifted apart
+        old.retain_mut(|p| {
+            let (a, b) = (pose_a_.transform_point(&p.local_a), pose_b_.transform_point(&p.local_b));
+            let depth = (a - b).dot(&n);
+            let drift = (a - b) - n * depth;
+            p.contact.point_a = a;
+            p.contact.point_b = b;
+            p.contact.depth = depth;
+            depth >= -threshold && drift.sqr_magnitude() <= threshold * threshold
+        });
+        let near = |p:&PersistentPoint<S>, c:&ContactPoint<S>| (p.contact.point_b - c.point_b).sqr_magnitude() <= threshold * threshold;
+        let mut points: Vec<PersistentPoint<S>> = Vec::with_capacity(m_.points.len() + old.len());
+        for c in &m_.points {
+            let mut p = PersistentPoint {
+                contact: c.clone(),
+                local_a: pose_a_.inverse_transform_point(&c.point_a),
+                local_b: pose_b_.inverse_transform_point(&c.point_b),
+                normal_impulse: S::ZERO,
+                tangent_impulse: Float3::zero(),
+                age: 0,
+            };
+            let matched = old.iter().position(|o| o.contact.feature() == c.feature())
+                .or_else(|| old.iter().position(|o| near(o, c)));
+            if let Some(i) = matched {
+                let o = old.swap_remove(i);
+                p.normal_impulse = o.normal_impulse;
+                p.tangent_impulse = o.tangent_impulse;
+                p.age = o.age + 1;
+            }
+            points.push(p);
+        }
+        for mut o in old {
+            if m_.points.iter().any(|c| near(&o, c)) { continue }
+            o.age += 1;
+            points.push(o);
+        }
+        reduce(&mut points, &n, |p| (p.contact.point_b, p.contact.depth));
+        let m = self.manifolds.entry(pair_).or_insert(PersistentManifold {normal:n, points:Vec::new()});
+        m.normal = n;
+        m.points = points;
+        Some(m)
+    }
+}
+
+impl<S> Default for ManifoldCache<S> where S: Scalar {
+    /// drops points drifting apart by more than 0.02, suited to objects about a unit across
+    fn default() -> Self { ManifoldCache::new(S::from_f32(0.02)) }
+}
+
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gjk3d;
+    use crate::shape::{OrientedBox, Posed, Sphere};
+    use crate::vector::Quat;
+
+    fn manifold<A, B>(a_:&A, b_:&B) -> ContactManifold where A: SupportMap<Float3>, B: SupportMap<Float3> {
+        let mut f = Frame3Simplex::new();
+        let r = gjk3d::check(a_, b_, &Float3::zero(), &mut f).unwrap();
+        contact_manifold(a_, b_, &r, &mut f)
+    }
+
+    #[test]
+    fn box_resting_on_box() {
+        //the bottom face of B sinks 0.1 into the top face of A, inside it
+        let a = OrientedBox::axis_aligned(Float3::zero(), Float3 {x:2.0, y:2.0, z:1.0});
+        let b = OrientedBox::axis_aligned(Float3 {x:0.3, y:-0.2, z:1.9}, Float3 {x:1.0, y:1.0, z:1.0});
+        let m = manifold(&a, &b);
+        assert!((m.normal - Float3 {x:0.0, y:0.0, z:1.0}).magnitude() <= 1e-4);
+        assert_eq!(m.points.len(), 4);
+        for p in &m.points {
+            assert!((p.depth - 0.1).abs() <= 1e-4);
+            assert!((p.point_a.z - 1.0).abs() <= 1e-4 && (p.point_b.z - 0.9).abs() <= 1e-4);
+            //the corners of the bottom face of B
+            assert!(((p.point_b.x - 0.3).abs() - 1.0).abs() <= 1e-4 && ((p.point_b.y + 0.2).abs() - 1.0).abs() <= 1e-4);
+        }
+    }
+
+    #[test]
+    fn box_turned_on_box() {
+        //the faces overlap in an octagon, reduced to four of its corners
+        let a = OrientedBox::axis_aligned(Float3::zero(), Float3 {x:1.0, y:1.0, z:1.0});
+        let turn = Isometry::new(Float3 {x:0.0, y:0.0, z:1.9}, Quat::from_scaled_axis(&Float3 {x:0.0, y:0.0, z:std::f32::consts::FRAC_PI_4}));
+        let b = Posed::new(OrientedBox::axis_aligned(Float3::zero(), Float3 {x:1.0, y:1.0, z:1.0}), turn);
+        let m = manifold(&a, &b);
+        assert_eq!(m.points.len(), 4);
+        for p in &m.points {
+            assert!((p.depth - 0.1).abs() <= 1e-4);
+            assert!(p.point_a.x.abs() <= 1.0 + 1e-4 && p.point_a.y.abs() <= 1.0 + 1e-4);
+            assert!(p.point_a.x.abs() + p.point_a.y.abs() <= std::f32::consts::SQRT_2 + 1e-4);
+        }
+    }
+
+    #[test]
+    fn sphere_on_box() {
+        let a = OrientedBox::axis_aligned(Float3::zero(), Float3 {x:1.0, y:1.0, z:1.0});
+        let b = Sphere {center:Float3 {x:0.2, y:0.3, z:1.4}, radius:0.5};
+        let m = manifold(&a, &b);
+        assert_eq!(m.points.len(), 1);
+        assert!((m.points[0].depth - 0.1).abs() <= 1e-4);
+        assert!((m.points[0].point_a - Float3 {x:0.2, y:0.3, z:1.0}).magnitude() <= 1e-3);
+    }
+
+    /// contact at `(x_, y_)` on the plane z = 0, B sunk 0.1 below A along the normal +z
+    fn contact(x_:f32, y_:f32, feature_:FeatureId) -> ContactPoint {
+        ContactPoint {
+            point_a: Float3 {x:x_, y:y_, z:0.0},
+            point_b: Float3 {x:x_, y:y_, z:-0.1},
+            depth: 0.1,
+            feature_a: feature_,
+            feature_b: NO_FEATURE,
+        }
+    }
+
+    fn contacts(point_:Vec<ContactPoint>) -> ContactManifold {
+        ContactManifold {normal:Float3 {x:0.0, y:0.0, z:1.0}, points:point_}
+    }
+
+    /// sets the normal impulse of every cached point of `pair_` to its first feature
+    fn mark(cache_:&mut ManifoldCache, pair_:(usize, usize)) {
+        for p in &mut cache_.get_mut(pair_).unwrap().points {
+            p.normal_impulse = p.contact.feature_a as f32;
+        }
+    }
+
+    #[test]
+    fn cache_matches_by_feature() {
+        let mut cache = ManifoldCache::new(0.02);
+        let pose = Isometry::identity();
+        cache.update((0, 1), &contacts(vec![contact(0.0, 0.0, 1), contact(1.0, 0.0, 2)]), &pose, &pose);
+        mark(&mut cache, (0, 1));
+        //both points moved further than the breaking threshold and swapped order, their features still tell them apart
+        let m = cache.update((0, 1), &contacts(vec![contact(1.05, 0.0, 2), contact(0.05, 0.0, 1)]), &pose, &pose).unwrap();
+        assert_eq!(m.points.len(), 2);
+        for p in &m.points {
+            assert_eq!(p.normal_impulse, p.contact.feature_a as f32);
+            assert_eq!(p.age, 1);
+        }
+    }
+
+    #[test]
+    fn cache_matches_by_distance() {
+        let mut cache = ManifoldCache::new(0.02);
+        let pose = Isometry::identity();
+        cache.update((0, 1), &contacts(vec![contact(0.0, 0.0, 1), contact(1.0, 0.0, 2)]), &pose, &pose);
+        mark(&mut cache, (0, 1));
+        //new features, within the breaking threshold of the cached points
+        let m = cache.update((0, 1), &contacts(vec![contact(1.01, 0.0, 7), contact(0.01, 0.0, 8)]), &pose, &pose).unwrap();
+        assert_eq!(m.points.len(), 2);
+        for p in &m.points {
+            let expected = if p.contact.point_a.x < 0.5 { 1.0 } else { 2.0 };
+            assert_eq!((p.normal_impulse, p.age), (expected, 1));
+        }
+        //far from both, a new point starts without impulse next to the cached ones
+        let m = cache.update((0, 1), &contacts(vec![contact(0.0, 1.0, 9)]), &pose, &pose).unwrap();
+        assert_eq!(m.points.len(), 3);
+        let new = m.points.iter().find(|p| p.contact.feature_a == 9).unwrap();
+        assert_eq!((new.normal_impulse, new.age), (0.0, 0));
+    }
+
+    #[test]
+    fn cache_drops_drifted_points() {
+        let mut cache = ManifoldCache::new(0.02);
+        let pose = Isometry::identity();
+        cache.update((0, 1), &contacts(vec![contact(0.0, 0.0, 1), contact(1.0, 0.0, 2), contact(0.0, 1.0, 3)]), &pose, &pose);
+        //B slides by less than the threshold: the cached points follow it and are kept though not reported again
+        let slide = Isometry::new(Float3 {x:0.01, y:0.0, z:0.0}, Quat::identity());
+        let m = cache.update((0, 1), &contacts(vec![contact(0.0, 0.0, 1)]), &pose, &slide).unwrap();
+        assert_eq!(m.points.len(), 3);
+        assert!(m.points.iter().all(|p| p.age == 1));
+        //sliding further, the sides of every cached point drift apart: they are dropped and the reported one starts over
+        let slide = Isometry::new(Float3 {x:0.05, y:0.0, z:0.0}, Quat::identity());
+        let m = cache.update((0, 1), &contacts(vec![contact(0.0, 0.0, 1)]), &pose, &slide).unwrap();
+        assert_eq!(m.points.iter().map(|p| (p.contact.feature_a, p.age)).collect::<Vec<_>>(), vec![(1, 0)]);
+        //B lifting off past the threshold also breaks a point
+        cache.update((0, 1), &contacts(vec![contact(0.0, 0.0, 1), contact(1.0, 0.0, 2)]), &pose, &pose);
+        let lift = Isometry::new(Float3 {x:0.0, y:0.0, z:0.15}, Quat::identity());
+        let m = cache.update((0, 1), &contacts(vec![contact(0.0, 0.0, 1)]), &pose, &lift).unwrap();
+        assert_eq!(m.points.len(), 1);
+        //an empty manifold forgets the pair
+        assert!(cache.update((0, 1), &contacts(Vec::new()), &pose, &pose).is_none());
+        assert!(cache.get((0, 1)).is_none());
+    }
+
+    #[test]
+    fn cache_keeps_four_points() {
+        let mut cache = ManifoldCache::new(0.02);
+        let pose = Isometry::identity();
+        cache.update((0, 1), &contacts(vec![contact(0.0, 0.0, 1), contact(2.0, 0.0, 2), contact(2.0, 2.0, 3)]), &pose, &pose);
+        //three more points inside or on the quad, one of them the deepest
+        let mut point = vec![contact(0.0, 2.0, 4), contact(1.0, 1.0, 5), contact(1.0, 0.0, 6)];
+        point[1].point_b.z = -0.3;
+        point[1].depth = 0.3;
+        let m = cache.update((0, 1), &contacts(point), &pose, &pose).unwrap();
+        let mut feature: Vec<_> = m.points.iter().map(|p| p.contact.feature_a).collect();
+        feature.sort_unstable();
+        //the deepest point, then the corners spanning the largest area with it
+        assert_eq!(feature.len(), 4);
+        assert!(feature.contains(&5));
+        assert!(!feature.contains(&6));
+    }
+
+    #[test]
+    fn default_cache() {
+        let cache = ManifoldCache::<f32>::default();
+        assert_eq!(cache.breaking_threshold, 0.02);
+        assert_eq!(cache.iter().count(), 0);
+    }
+}
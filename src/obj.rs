@@ -0,0 +1,230 @@
+use std::fs::File;
+use std::io::{self, BufRead};
+use std::path::Path;
+use crate::vector::{Float3, Isometry, Scalar};
+use crate::simplex_based_cd_helper::Error;
+use crate::hull::ConvexHull;
+use crate::world::Object;
+
+// region ObjShape
+
+/// Placement given to every shape read from an OBJ file.
+#[derive(Debug, Clone, Copy)]
+pub struct ObjOptions<S = f32> where S: Scalar {
+    /// position and rotation of the file coordinates
+    pub pose: Isometry<S>,
+    pub linear: Float3<S>,
+    /// world space, around the origin of the file coordinates
+    pub angular: Float3<S>,
+}
+
+impl<S> Default for ObjOptions<S> where S: Scalar {
+    fn default() -> Self {
+        ObjOptions {pose:Isometry::identity(), linear:Float3::zero(), angular:Float3::zero()}
+    }
+}
+
+/// Convex hull of an object or group of an OBJ file, in the file coordinates, placed by `pose`.
+#[derive(Debug)]
+pub struct ObjShape<S = f32> where S: Scalar {
+    /// name of its `o` or `g` statement, empty for the part of the file before the first one
+    pub name: String,
+    pub hull: ConvexHull<S>,
+    pub pose: Isometry<S>,
+    pub linear: Float3<S>,
+    pub angular: Float3<S>,
+}
+
+impl<S> ObjShape<S> where S: Scalar + 'static {
+    /// the shape as an object to add to a `World`
+    pub fn into_object(self) -> Object<S> {
+        let mut ret = Object::new(self.hull, self.pose);
+        ret.linear = self.linear;
+        ret.angular = self.angular;
+        ret
+    }
+}
+
+#[derive(Debug)]
+pub enum ObjError {
+    IO(io::Error),
+    /// line number and what is wrong with it
+    Syntax(usize, String),
+    /// line number of the `o` or `g` statement, its name and why it has no hull
+    Hull(usize, String, Error),
+}
+
+impl std::fmt::Display for ObjError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ObjError::IO(e) => write!(f, "io error: {}", e),
+            ObjError::Syntax(l, s) => write!(f, "line {}: {}", l, s),
+            ObjError::Hull(l, n, e) => write!(f, "line {}: object '{}' has no convex hull ({:?}), its points must span a volume", l, n, e),
+        }
+    }
+}
+
+// endregion
+
+// region parse
+
+/// vertices and faces gathered since an `o` or `g` statement
+struct Group {
+    name: String,
+    line: usize,
+    /// indices into the vertices of the file
+    vertex: Vec<usize>,
+    face_vertex: Vec<usize>,
+}
+
+impl Group {
+    fn new(name_:&str, line_:usize) -> Group {
+        Group {name:name_.to_string(), line:line_, vertex:Vec::new(), face_vertex:Vec::new()}
+    }
+}
+
+/// Reads the Wavefront OBJ file at `path_`, see `parse_obj`.
+pub fn read_obj<S, P>(path_:P, options_:&ObjOptions<S>) -> Result<Vec<ObjShape<S>>, ObjError>
+    where S: Scalar, P: AsRef<Path>
+{
+    let file = File::open(path_).map_err(ObjError::IO)?;
+    parse_obj(io::BufReader::new(file), options_)
+}
+
+/// Builds one convex shape per object or group of a Wavefront OBJ file, in file order.
+///
+/// When the file has faces, a group takes the vertices its faces refer to and groups without faces are skipped,
+/// so vertices shared by several groups or listed under an `o` whose faces follow a `g` belong to the right shapes.
+/// Files without faces take the `v` statements inside each group instead.
+/// Statements other than `v`, `f`, `o` and `g` are skipped, and so are groups without vertices.
+pub fn parse_obj<S, R>(reader_:R, options_:&ObjOptions<S>) -> Result<Vec<ObjShape<S>>, ObjError>
+    where S: Scalar, R: BufRead
+{
+    let mut vertex:Vec<Float3<S>> = Vec::new();
+    let mut group = vec![Group::new("", 1)];
+    for (i, line) in reader_.lines().enumerate() {
+        let line = line.map_err(ObjError::IO)?;
+        let number = i + 1;
+        let line = line.split('#').next().unwrap_or("");
+        let mut token = line.split_whitespace();
+        let Some(keyword) = token.next() else { continue };
+        let current = group.last_mut().unwrap();
+        match keyword {
+            "v" => {
+                let mut c = Vec::with_capacity(4);
+                for t in token {
+                    //finite in the scalar type, `1e39` overflows `f32`
+                    let v = t.parse::<f64>().ok().map(S::from_f64).filter(|v| v.to_f64().is_finite());
+                    c.push(v.ok_or_else(|| ObjError::Syntax(number, format!("invalid coordinate '{}'", t)))?);
+                }
+                //`x y z`, with an optional weight or vertex color
+                if !matches!(c.len(), 3 | 4 | 6 | 7) {
+                    return Err(ObjError::Syntax(number, format!("v expects 3 coordinates, found {}", c.len())))
+                }
+                current.vertex.push(vertex.len());
+                vertex.push(Float3 {x:c[0], y:c[1], z:c[2]});
+            },
+            "f" => {
+                let start = current.face_vertex.len();
+                for t in token {
+                    let index = parse_index(t, vertex.len()).map_err(|s| ObjError::Syntax(number, s))?;
+                    current.face_vertex.push(index);
+                }
+                if current.face_vertex.len() - start < 3 {
+                    return Err(ObjError::Syntax(number, format!("f expects at least 3 vertices, found {}", current.face_vertex.len() - start)))
+                }
+            },
+            "o" | "g" => {
+                let name = token.collect::<Vec<_>>().join(" ");
+                group.push(Group::new(&name, number));
+            },
+            _ => {},
+        }
+    }
+    let has_face = group.iter().any(|g| !g.face_vertex.is_empty());
+    let mut ret = Vec::new();
+    let mut point = Vec::new();
+    for g in group {
+        let Group {name, line, vertex:mut index, face_vertex} = g;
+        if has_face { index = face_vertex; }
+        if index.is_empty() { continue }
+        index.sort_unstable();
+        index.dedup();
+        point.clear();
+        point.extend(index.iter().map(|&k| vertex[k]));
+        let hull = ConvexHull::from_points(&point).map_err(|e| ObjError::Hull(line, name.clone(), e))?;
+        ret.push(ObjShape {name, hull, pose:options_.pose, linear:options_.linear, angular:options_.angular});
+    }
+    Ok(ret)
+}
+
+/// vertex of a face corner `v`, `v/vt`, `v//vn` or `v/vt/vn`, counted from 1 or from the end when negative
+fn parse_index(token_:&str, count_:usize) -> Result<usize, String> {
+    let invalid = || format!("invalid face vertex '{}'", token_);
+    let mut part = token_.split('/');
+    let index = part.next().unwrap_or("").parse::<i64>().map_err(|_| invalid())?;
+    //texture and normal indices are not used but must be well formed, the texture one may be left out
+    let is_index = |p:&str| p.parse::<i64>().is_ok();
+    let valid = match part.collect::<Vec<_>>().as_slice() {
+        [] => true,
+        [vt] => is_index(vt),
+        [vt, vn] => (vt.is_empty() || is_index(vt)) && is_index(vn),
+        _ => false,
+    };
+    if !valid { return Err(invalid()) }
+    let ret = if index < 0 { count_ as i64 + index } else { index - 1 };
+    if index == 0 || ret < 0 || ret >= count_ as i64 {
+        return Err(format!("face vertex {} out of range, {} vertices defined so far", index, count_))
+    }
+    Ok(ret as usize)
+}
+
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CUBE_VERTEX: &str = "v 0 0 0\nv 1 0 0\nv 0 1 0\nv 1 1 0\nv 0 0 1\nv 1 0 1\nv 0 1 1\nv 1 1 1\n";
+
+    fn parse(text_:&str) -> Result<Vec<ObjShape>, ObjError> {
+        parse_obj(text_.as_bytes(), &ObjOptions::default())
+    }
+
+    /// names of the shapes and the number of hull vertices of each
+    fn summary(shape_:&[ObjShape]) -> Vec<(String, usize)> {
+        shape_.iter().map(|s| (s.name.clone(), s.hull.vertex.len())).collect()
+    }
+
+    #[test]
+    fn groups_sharing_vertices() {
+        //one vertex block, each group taking the tetrahedron at one corner of the cube
+        let text = format!("{}g a\nf 1 2 3\nf 1 2 5\nf 1 3 5\nf 2 3 5\ng b\nf 8 7 6\nf 8 7 4\nf 8 6 4\nf 7 6 4\n", CUBE_VERTEX);
+        assert_eq!(summary(&parse(&text).unwrap()), vec![("a".to_string(), 4), ("b".to_string(), 4)]);
+    }
+
+    #[test]
+    fn object_with_material_group() {
+        //as written by Blender: the vertices under `o`, the faces under a `g` named after the object and its material
+        let text = format!("o Cube\n{}g Cube_Material\nf 1 2 4 3\nf 5 6 8 7\nf 1 2 6 5\nf 3 4 8 7\nf 1 3 7 5\nf 2 4 8 6\n", CUBE_VERTEX);
+        assert_eq!(summary(&parse(&text).unwrap()), vec![("Cube_Material".to_string(), 8)]);
+    }
+
+    #[test]
+    fn groups_without_faces() {
+        let text = format!("o a\n{}o b\nv 5 5 5\nv 6 5 5\nv 5 6 5\nv 5 5 6\n", CUBE_VERTEX);
+        assert_eq!(summary(&parse(&text).unwrap()), vec![("a".to_string(), 8), ("b".to_string(), 4)]);
+    }
+
+    #[test]
+    fn non_finite_coordinates() {
+        for c in ["nan", "inf", "-inf", "1e400"] {
+            let text = format!("v 0 0 0\nv 1 0 {}\n", c);
+            assert!(matches!(parse(&text), Err(ObjError::Syntax(2, _))), "{}", c);
+        }
+        let text = "v 0 0 0\nv 1 0 1e39\n";
+        assert!(matches!(parse(text), Err(ObjError::Syntax(2, _))));
+        //in range for f64, the file then fails for having a single point
+        assert!(matches!(parse_obj::<f64, _>(text.as_bytes(), &ObjOptions::default()), Err(ObjError::Hull(..))));
+    }
+}
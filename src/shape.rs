@@ -0,0 +1,274 @@
+use crate::vector::{Float3, Isometry, Mat3, Scalar};
+use crate::support_map::{FeatureId, SupportMap, FACE_TOLERANCE};
+
+fn unit_or_x<S>(d_:&Float3<S>) -> Float3<S> where S: Scalar {
+    let m = d_.magnitude();
+    if m > S::ZERO { d_.scaled(S::ONE, m) } else { Float3 {x:S::ONE, y:S::ZERO, z:S::ZERO} }
+}
+
+/// component of `d_` perpendicular to the unit vector `axis_`, normalized;
+/// zero when `d_` is parallel to the axis
+fn radial<S>(d_:&Float3<S>, axis_:&Float3<S>) -> Float3<S> where S: Scalar {
+    let r = *d_ - *axis_ * d_.dot(axis_);
+    let m = r.magnitude();
+    if m > S::ZERO { r.scaled(S::ONE, m) } else { Float3::zero() }
+}
+
+// region Sphere
+
+#[derive(Debug, Clone, Copy)]
+pub struct Sphere<S = f32> {
+    pub center: Float3<S>,
+    pub radius: S,
+}
+
+impl<S> SupportMap<Float3<S>> for Sphere<S> where S: Scalar {
+    fn support(&self, d_:&Float3<S>) -> (Float3<S>, FeatureId) {
+        (self.center + unit_or_x(d_) * self.radius, 0)
+    }
+}
+
+// endregion
+
+// region Capsule
+
+/// Segment `p0`-`p1` swept by a sphere of `radius`.
+#[derive(Debug, Clone, Copy)]
+pub struct Capsule<S = f32> {
+    pub p0: Float3<S>,
+    pub p1: Float3<S>,
+    pub radius: S,
+}
+
+impl<S> SupportMap<Float3<S>> for Capsule<S> where S: Scalar {
+    fn support(&self, d_:&Float3<S>) -> (Float3<S>, FeatureId) {
+        let (p, id) = if self.p1.dot(d_) > self.p0.dot(d_) { (self.p1, 1) } else { (self.p0, 0) };
+        (p + unit_or_x(d_) * self.radius, id)
+    }
+}
+
+// endregion
+
+// region Cylinder
+
+/// Cylinder around the unit `axis` through `center`, spanning `half_height` to either side.
+#[derive(Debug, Clone, Copy)]
+pub struct Cylinder<S = f32> {
+    pub center: Float3<S>,
+    pub axis: Float3<S>,
+    pub half_height: S,
+    pub radius: S,
+}
+
+impl<S> SupportMap<Float3<S>> for Cylinder<S> where S: Scalar {
+    /// feature 1 is the top rim (along `axis`), 0 the bottom rim
+    fn support(&self, d_:&Float3<S>) -> (Float3<S>, FeatureId) {
+        let (h, id) = if d_.dot(&self.axis) > S::ZERO { (self.half_height, 1) } else { (-self.half_height, 0) };
+        (self.center + self.axis * h + radial(d_, &self.axis) * self.radius, id)
+    }
+}
+
+// endregion
+
+// region Cone
+
+/// Cone around the unit `axis` through `center`, with its apex `half_height` along `axis`
+/// and a base disc of `radius` `half_height` against it.
+#[derive(Debug, Clone, Copy)]
+pub struct Cone<S = f32> {
+    pub center: Float3<S>,
+    pub axis: Float3<S>,
+    pub half_height: S,
+    pub radius: S,
+}
+
+impl<S> SupportMap<Float3<S>> for Cone<S> where S: Scalar {
+    /// feature 1 is the apex, 0 the base rim
+    fn support(&self, d_:&Float3<S>) -> (Float3<S>, FeatureId) {
+        let apex = self.center + self.axis * self.half_height;
+        let rim = self.center - self.axis * self.half_height + radial(d_, &self.axis) * self.radius;
+        if apex.dot(d_) >= rim.dot(d_) { (apex, 1) } else { (rim, 0) }
+    }
+}
+
+// endregion
+
+// region OrientedBox
+
+/// Box with orthonormal `axes` and `half_extents` measured along each of them.
+#[derive(Debug, Clone, Copy)]
+pub struct OrientedBox<S = f32> {
+    pub center: Float3<S>,
+    pub axes: [Float3<S>; 3],
+    pub half_extents: Float3<S>,
+}
+
+impl<S> OrientedBox<S> where S: Scalar {
+    pub fn axis_aligned(center_:Float3<S>, half_extents_:Float3<S>) -> OrientedBox<S> {
+        OrientedBox {
+            center: center_,
+            axes: [
+                Float3 {x:S::ONE, y:S::ZERO, z:S::ZERO},
+                Float3 {x:S::ZERO, y:S::ONE, z:S::ZERO},
+                Float3 {x:S::ZERO, y:S::ZERO, z:S::ONE},
+            ],
+            half_extents: half_extents_,
+        }
+    }
+
+    /// corner `id_`, bit i set means the positive side of axis i
+    pub fn vertex(&self, id_:FeatureId) -> Float3<S> {
+        let h = [self.half_extents.x, self.half_extents.y, self.half_extents.z];
+        let mut p = self.center;
+        for (i, axis) in self.axes.iter().enumerate() {
+            p = p + *axis * if id_ & (1 << i) != 0 { h[i] } else { -h[i] };
+        }
+        p
+    }
+}
+
+impl<S> SupportMap<Float3<S>> for OrientedBox<S> where S: Scalar {
+    fn support(&self, d_:&Float3<S>) -> (Float3<S>, FeatureId) {
+        let mut id = 0;
+        for (i, axis) in self.axes.iter().enumerate() {
+            if axis.dot(d_) > S::ZERO { id |= 1 << i; }
+        }
+        (self.vertex(id), id)
+    }
+
+    /// reports every corner of the face or edge perpendicular to `d_`, like a vertex cloud would
+    fn support_candidates(&self, d_:&Float3<S>, candidate_:&mut Vec<(Float3<S>, FeatureId)>) {
+        candidate_.clear();
+        let mut id = 0;
+        let mut free = 0;
+        for (i, axis) in self.axes.iter().enumerate() {
+            let c = axis.dot(d_);
+            if c > S::ZERO { id |= 1 << i; }
+            else if c == S::ZERO { free |= 1 << i; }
+        }
+        for corner in 0..8 {
+            if corner & !free == id {
+                candidate_.push((self.vertex(corner), corner));
+            }
+        }
+    }
+
+    /// like `support_candidates`, with axes nearly perpendicular to `d_` counted as free
+    fn support_face(&self, d_:&Float3<S>, face_:&mut Vec<(Float3<S>, FeatureId)>) {
+        face_.clear();
+        let d = unit_or_x(d_);
+        let tolerance = S::from_f32(FACE_TOLERANCE);
+        let mut id = 0;
+        let mut free = 0;
+        for (i, axis) in self.axes.iter().enumerate() {
+            let c = axis.dot(&d);
+            if c.abs() <= tolerance { free |= 1 << i; }
+            else if c > S::ZERO { id |= 1 << i; }
+        }
+        for corner in 0..8 {
+            if corner & !free == id {
+                face_.push((self.vertex(corner), corner));
+            }
+        }
+    }
+
+    fn feature_point(&self, id_:FeatureId) -> Option<Float3<S>> {
+        if id_ < 8 { Some(self.vertex(id_)) } else { None }
+    }
+}
+
+// endregion
+
+// region Ellipsoid
+
+/// Ellipsoid with orthonormal `axes` and `radii` measured along each of them.
+#[derive(Debug, Clone, Copy)]
+pub struct Ellipsoid<S = f32> {
+    pub center: Float3<S>,
+    pub axes: [Float3<S>; 3],
+    pub radii: Float3<S>,
+}
+
+impl<S> SupportMap<Float3<S>> for Ellipsoid<S> where S: Scalar {
+    fn support(&self, d_:&Float3<S>) -> (Float3<S>, FeatureId) {
+        let r = [self.radii.x, self.radii.y, self.radii.z];
+        //scale the local direction by the radii, the support point is the scaled sphere support
+        let mut l = [S::ZERO; 3];
+        for (i, axis) in self.axes.iter().enumerate() {
+            l[i] = axis.dot(d_) * r[i];
+        }
+        let m = (l[0] * l[0] + l[1] * l[1] + l[2] * l[2]).sqrt();
+        if m <= S::ZERO { return (self.center + self.axes[0] * r[0], 0) }
+        let mut p = self.center;
+        for (i, axis) in self.axes.iter().enumerate() {
+            p = p + *axis * (l[i] * r[i] / m);
+        }
+        (p, 0)
+    }
+}
+
+// endregion
+
+// region Posed
+
+/// A shape defined in its local space, placed in the world by `pose`.
+/// Support queries are transformed on the fly, so moving the shape never touches its data.
+#[derive(Debug, Clone)]
+pub struct Posed<T, S = f32> {
+    pub shape: T,
+    pose: Isometry<S>,
+    basis: Mat3<S>,
+}
+
+impl<T, S> Posed<T, S> where S: Scalar {
+    pub fn new(shape_:T, pose_:Isometry<S>) -> Posed<T, S> {
+        Posed {shape:shape_, pose:pose_, basis:Mat3::from_quat(&pose_.rotation)}
+    }
+
+    pub fn pose(&self) -> &Isometry<S> { &self.pose }
+
+    pub fn set_pose(&mut self, pose_:Isometry<S>) {
+        self.pose = pose_;
+        self.basis = Mat3::from_quat(&pose_.rotation);
+    }
+
+    fn to_world(&self, p_:&Float3<S>) -> Float3<S> {
+        self.basis.mul_vec(p_) + self.pose.translation
+    }
+}
+
+impl<T, S> SupportMap<Float3<S>> for Posed<T, S> where S: Scalar, T: SupportMap<Float3<S>> {
+    fn support(&self, d_:&Float3<S>) -> (Float3<S>, FeatureId) {
+        let (p, id) = self.shape.support(&self.basis.transpose_mul_vec(d_));
+        (self.to_world(&p), id)
+    }
+
+    fn support_candidates(&self, d_:&Float3<S>, candidate_:&mut Vec<(Float3<S>, FeatureId)>) {
+        self.shape.support_candidates(&self.basis.transpose_mul_vec(d_), candidate_);
+        for (p, _) in candidate_.iter_mut() {
+            *p = self.to_world(p);
+        }
+    }
+
+    fn support_candidates_from(&self, d_:&Float3<S>, start_:&mut Option<FeatureId>, candidate_:&mut Vec<(Float3<S>, FeatureId)>) {
+        self.shape.support_candidates_from(&self.basis.transpose_mul_vec(d_), start_, candidate_);
+        for (p, _) in candidate_.iter_mut() {
+            *p = self.to_world(p);
+        }
+    }
+
+    fn support_face(&self, d_:&Float3<S>, face_:&mut Vec<(Float3<S>, FeatureId)>) {
+        self.shape.support_face(&self.basis.transpose_mul_vec(d_), face_);
+        for (p, _) in face_.iter_mut() {
+            *p = self.to_world(p);
+        }
+    }
+
+    fn feature_point(&self, id_:FeatureId) -> Option<Float3<S>> {
+        self.shape.feature_point(id_).map(|p| self.to_world(&p))
+    }
+
+    fn is_valid(&self) -> bool { self.shape.is_valid() }
+}
+
+// endregion
@@ -1,22 +1,30 @@
-use crate::vector::Float3;
+use std::collections::HashMap;
+use crate::vector::{Float3, Scalar, Vector};
+use crate::support_map::{FeatureId, SupportMap};
 type Point<T> = MinkowskiSumPoint<T>;
 
 #[derive(Debug)]
+#[allow(clippy::upper_case_acronyms)]
 pub enum Error {
     InvalidInput,
-    SimplexSearch,
     NearestSimplexSearch,
-    NearestFeatureSearch,
     EPA,
+    RayCast,
+    TimeOfImpact,
 }
 
 // region MinkowskiSumPoint
 
+/// A point on the Minkowski difference `A - B`, with the support points it came from.
 #[derive(Debug, Clone)]
 pub struct MinkowskiSumPoint<T> {
     pub v: T,
-    pub a: usize,
-    pub b: usize,
+    /// support point on shape A
+    pub pa: T,
+    /// support point on shape B
+    pub pb: T,
+    pub a: FeatureId,
+    pub b: FeatureId,
 }
 
 impl<T> std::fmt::Display for MinkowskiSumPoint<T> where T: std::fmt::Display {
@@ -32,129 +40,311 @@ impl<T> std::cmp::PartialEq for MinkowskiSumPoint<T> {
 }
 
 impl<T> Default for MinkowskiSumPoint<T> where T: Default {
-    fn default() -> Self { MinkowskiSumPoint {v:Default::default(), a:0, b:0}}
+    fn default() -> Self { MinkowskiSumPoint {v:Default::default(), pa:Default::default(), pb:Default::default(), a:0, b:0}}
 }
 
 // endregion
 
 // region Frame3Simplex
 
+/// Scratch memory reused across queries, it holds nothing meaningful once a query returns.
 #[derive(Debug)]
-pub struct Frame3Simplex<T> {
-    pub mtv: T,
-    pub min_dist: f32,
-    pub s0: Point<T>,
-    pub s1: Point<T>,
-    pub s2: Point<T>,
-    pub closest_a: T,
-    pub closest_b: T,
+pub struct Frame3Simplex<T> where T: Vector {
     pub cache_epa: Vec<EPA2Simplex<T>>,
-    pub candidate_a: Vec<usize>,
-    pub candidate_b: Vec<usize>,
+    pub cache_edge: Vec<(Point<T>, Point<T>)>,
+    pub candidate_a: Vec<(T, FeatureId)>,
+    pub candidate_b: Vec<(T, FeatureId)>,
+    /// feature each shape's last support query ended on, see `SupportMap::support_candidates_from`
+    pub start_a: Option<FeatureId>,
+    pub start_b: Option<FeatureId>,
 }
 
-impl<T> Frame3Simplex<T> where T : Default {
+impl<T> Frame3Simplex<T> where T: Vector {
     pub fn new() -> Frame3Simplex<T> {
         Frame3Simplex {
-            mtv: Default::default(),
-            min_dist: 0.0,
-            s0: Default::default(),
-            s1: Default::default(),
-            s2: Default::default(),
-            closest_a: Default::default(),
-            closest_b: Default::default(),
             cache_epa: Vec::new(),
+            cache_edge: Vec::new(),
             candidate_a: Vec::new(),
             candidate_b: Vec::new(),
+            start_a: None,
+            start_b: None,
         }
     }
 }
 
-impl<T> Frame3Simplex<T> {
-    pub fn cache(&mut self, v0:Point<T>, v1:Point<T>, v2:Point<T>) {
-        self.s0 = v0;
-        self.s1 = v1;
-        self.s2 = v2;
+impl<T> Default for Frame3Simplex<T> where T: Vector {
+    fn default() -> Self { Frame3Simplex::new() }
+}
+
+// endregion
+
+// region CoherentFrame
+
+/// Scratch memory of one object pair for coherent queries, together with what the last query on that pair found.
+/// Reusing it frame after frame lets the next query start where the last one ended.
+#[derive(Debug, Default)]
+pub struct CoherentFrame<T> where T: Vector {
+    pub frame: Frame3Simplex<T>,
+    /// result of the last query, `None` until the first one or after an error
+    pub last: Option<CollisionResult<T>>,
+}
+
+impl<T> CoherentFrame<T> where T: Vector {
+    pub fn new() -> CoherentFrame<T> {
+        CoherentFrame {frame:Frame3Simplex::new(), last:None}
+    }
+}
+
+/// One `CoherentFrame` per object pair, keyed by a pair of object ids chosen by the caller.
+#[derive(Debug, Default)]
+pub struct CoherentCache<T> where T: Vector {
+    frames: HashMap<(usize, usize), CoherentFrame<T>>,
+}
+
+impl<T> CoherentCache<T> where T: Vector {
+    pub fn new() -> CoherentCache<T> {
+        CoherentCache {frames:HashMap::new()}
+    }
+
+    /// frame of `pair_`, created empty on first use
+    pub fn frame(&mut self, pair_:(usize, usize)) -> &mut CoherentFrame<T> {
+        self.frames.entry(pair_).or_default()
+    }
+
+    /// forgets `pair_`, once its objects are removed or too far apart to be queried
+    pub fn remove(&mut self, pair_:(usize, usize)) {
+        self.frames.remove(&pair_);
+    }
+
+    /// forgets every pair involving `id_`, once that object is removed
+    pub fn remove_object(&mut self, id_:usize) {
+        self.frames.retain(|p, _| p.0 != id_ && p.1 != id_);
+    }
+
+    /// forgets every pair missing from `pair_`, sorted as a broadphase reports its candidates
+    pub fn retain(&mut self, pair_:&[(usize, usize)]) {
+        self.frames.retain(|p, _| pair_.binary_search(p).is_ok());
+    }
+}
+
+// endregion
+
+// region CollisionResult
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CollisionStatus {
+    /// the shapes are apart, `distance` is the gap between them
+    Disjoint,
+    /// the shapes share boundary points only, up to rounding
+    Touching,
+    /// the shapes overlap, `-distance` is the penetration depth
+    Penetrating,
+}
+
+/// Outcome of `check`.
+///
+/// Follows the conventions of `DistanceResult`: `closest_b == closest_a + normal * distance`,
+/// with `normal` pointing from A towards B and `distance` negative when penetrating.
+#[derive(Debug, Clone)]
+pub struct CollisionResult<T> where T: Vector {
+    pub status: CollisionStatus,
+    /// unit vector from A towards B, zero when it is undefined
+    pub normal: T,
+    /// gap between the shapes, minus the penetration depth when penetrating
+    pub distance: T::Scalar,
+    /// witness point on A
+    pub closest_a: T,
+    /// witness point on B
+    pub closest_b: T,
+    /// simplex GJK finished on: the sub-simplex nearest to the origin when disjoint,
+    /// the simplex enclosing the origin otherwise
+    pub simplex: Vec<Point<T>>,
+    /// GJK and EPA iterations spent on the query
+    pub iteration: usize,
+    /// false when EPA ran out of iterations before the depth settled, as with curved shapes deep inside each other;
+    /// the result then comes from the nearest face found and the depth falls short of the true one
+    pub converged: bool,
+}
+
+impl<T> CollisionResult<T> where T: Vector {
+    /// builds the result of a nearest simplex search, `w_` being the barycentric weights of `s_`
+    pub fn from_nearest_simplex(s_:Vec<Point<T>>, w_:&[T::Scalar], iteration_:usize) -> CollisionResult<T> {
+        let mut closest_a = T::default();
+        let mut closest_b = T::default();
+        for (p, w) in s_.iter().zip(w_.iter()) {
+            closest_a = closest_a + p.pa * *w;
+            closest_b = closest_b + p.pb * *w;
+        }
+        if s_.len() > T::DIMENSION || encloses_origin(&s_, &(closest_a - closest_b)) {
+            //both points are the same up to rounding
+            closest_b = closest_a;
+        }
+        let ab = closest_b - closest_a;
+        let distance = ab.sqr_magnitude().sqrt();
+        let (status, normal) = if distance > T::Scalar::ZERO {
+            (CollisionStatus::Disjoint, ab * (T::Scalar::ONE / distance))
+        } else { (CollisionStatus::Touching, T::default()) };
+        CollisionResult {status, normal, distance, closest_a, closest_b, simplex: s_, iteration: iteration_, converged: true}
+    }
+
+    /// builds the result of EPA, `normal_` being the unit normal of the boundary of `A - B` nearest to the origin
+    /// and `scale_` the size of `A - B` the depth is compared against
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn from_penetration(
+        closest_a_:T, closest_b_:T, normal_:T, depth_:T::Scalar, scale_:T::Scalar,
+        simplex_:Vec<Point<T>>, iteration_:usize, converged_:bool
+    ) -> CollisionResult<T> {
+        //max may keep -0, which prints as a negative depth for touching shapes
+        let depth = if depth_ > T::Scalar::ZERO { depth_ } else { T::Scalar::ZERO };
+        CollisionResult {
+            status: if depth <= scale_ * T::Scalar::TOLERANCE { CollisionStatus::Touching } else { CollisionStatus::Penetrating },
+            normal: normal_,
+            distance: T::Scalar::ZERO - depth,
+            closest_a: closest_a_,
+            closest_b: closest_b_,
+            simplex: simplex_,
+            iteration: iteration_,
+            converged: converged_,
+        }
+    }
+
+    /// whether the shapes touch or overlap
+    pub fn is_colliding(&self) -> bool {
+        self.status != CollisionStatus::Disjoint
+    }
+
+    /// penetration depth, zero unless penetrating
+    pub fn depth(&self) -> T::Scalar {
+        if self.distance < T::Scalar::ZERO { -self.distance } else { T::Scalar::ZERO }
+    }
+}
+
+// endregion
+
+// region DistanceResult
+
+/// Outcome of a distance or signed distance query.
+///
+/// Both queries keep `closest_b == closest_a + normal * distance`, with `normal` pointing from A towards B:
+/// moving B by `-normal * distance` brings the shapes into touching contact.
+///
+/// `gjk3d::distance` never goes negative: when the shapes overlap `distance` is zero,
+/// `closest_a` and `closest_b` are the same point inside both shapes and `normal` is zero.
+/// `gjk3d::signed_distance` reports the penetration depth as a negative `distance`,
+/// with `closest_a` and `closest_b` the deepest points of each shape inside the other.
+#[derive(Debug, Clone)]
+pub struct DistanceResult<T> where T: Vector {
+    /// gap between the shapes, negative for the penetration depth of a signed query
+    pub distance: T::Scalar,
+    /// witness point on A
+    pub closest_a: T,
+    /// witness point on B
+    pub closest_b: T,
+    /// unit vector from A towards B, zero when it is undefined
+    pub normal: T,
+}
+
+impl<T> DistanceResult<T> where T: Vector {
+    pub fn is_overlapping(&self) -> bool {
+        self.distance <= T::Scalar::ZERO
+    }
+}
+
+impl<T> From<CollisionResult<T>> for DistanceResult<T> where T: Vector {
+    fn from(r_:CollisionResult<T>) -> Self {
+        DistanceResult {
+            distance: r_.distance,
+            closest_a: r_.closest_a,
+            closest_b: r_.closest_b,
+            normal: r_.normal,
+        }
     }
 }
 
 // endregion
 
+// region RayHit
+
+/// Where a ray `origin + dir * fraction` first meets a shape.
+#[derive(Debug, Clone)]
+pub struct RayHit<T> where T: Vector {
+    /// position of the hit along the ray, in units of its direction
+    pub fraction: T::Scalar,
+    pub point: T,
+    /// unit surface normal at `point`, zero when the ray starts inside the shape
+    pub normal: T,
+}
+
+// endregion
+
+// region ShapeCastHit
+
+/// First contact of two moving shapes.
+#[derive(Debug, Clone)]
+pub struct ShapeCastHit<T> where T: Vector {
+    /// time of impact, within the time step of the query
+    pub toi: T::Scalar,
+    /// unit contact normal from A towards B, zero when the shapes overlap from the start
+    pub normal: T,
+    /// witness point on A at `toi`
+    pub closest_a: T,
+    /// witness point on B at `toi`
+    pub closest_b: T,
+}
+
+// endregion
+
 // region EPA2Simplex
 
+/// A triangular face of the expanding polytope, wound counter clockwise seen from outside.
 #[derive(Debug, Clone)]
-pub struct EPA2Simplex<T> {
+pub struct EPA2Simplex<T> where T: Vector {
     pub v0: Point<T>,
     pub v1: Point<T>,
     pub v2: Point<T>,
+    /// unit outward normal
     pub n: T,
+    /// projection of the origin onto the face plane
     pub p: T,
-    pub e: T,
-    pub d0: f32,
-    pub d1: f32,
-    pub d2: f32,
-    pub d_sqr: f32,
-}
-
-impl EPA2Simplex<Float3> {
-    pub fn new(v0_:&Point<Float3>, v1_:&Point<Float3>, v2_:&Point<Float3>) -> EPA2Simplex<Float3> {
-        let n = Float3::triangle_normal(&v0_.v, &v1_.v, &v2_.v);
-        let e0 = v1_.v - v0_.v;
-        let n0 = Float3::cross(&e0, &n);
-        let e1 = v2_.v - v1_.v;
-        let n1 = Float3::cross(&e1, &n);
-        let e2 = v0_.v - v2_.v;
-        let n2 = Float3::cross(&e2, &n);
-        let mut d0 = -n0.dot(&v0_.v);
-        let mut d1 = -n1.dot(&v1_.v);
-        let mut d2 = -n2.dot(&v2_.v);
-        let e: Float3;
-        let mut p: Float3;
-        if d0 >= 0.0 && !e0.is_zero() {
-            d2 = 0.0;
-            e = e0;
-            d0 = e.dot(&v0_.v);
-            d1 = e0.sqr_magnitude();
-            p = v0_.v + e0.scaled(d0, d1);
-        }
-        else if d1 >= 0.0 && !e1.is_zero() {
-            d2 = 1.0;
-            e = e1;
-            d0 = e.dot(&v1_.v);
-            d1 = e1.sqr_magnitude();
-            p = v1_.v + e1.scaled(d0, d1);
-        }
-        else if d2 >= 0.0 && !e2.is_zero() {
-            d2 = 2.0;
-            e = e2;
-            d0 = e.dot(&v2_.v);
-            d1 = e2.sqr_magnitude();
-            p = v2_.v + e2.scaled(d0, d1);
-        }
-        else {
-            d2 = -1.0;
-            e = e0;
-            p = n;
-            p.scale(n.dot(&v0_.v), n.sqr_magnitude());
-        }
+    /// signed distance from the origin to the face plane along `n`
+    pub d: T::Scalar,
+}
+
+impl<S> EPA2Simplex<Float3<S>> where S: Scalar {
+    pub fn new(v0_:&Point<Float3<S>>, v1_:&Point<Float3<S>>, v2_:&Point<Float3<S>>) -> EPA2Simplex<Float3<S>> {
+        let mut n = Float3::triangle_normal(&v0_.v, &v1_.v, &v2_.v);
+        let m = n.magnitude();
+        //a degenerate face is never the nearest one and never visible
+        let d = if m > S::ZERO {
+            n.scale(S::ONE, m);
+            n.dot(&v0_.v)
+        } else { S::MAX };
         EPA2Simplex {
             v0: v0_.clone(),
             v1: v1_.clone(),
             v2: v2_.clone(),
-            n: n,
-            p: p,
-            e: e,
-            d0: d0,
-            d1: d1,
-            d2: d2,
-            d_sqr: p.sqr_magnitude(),
+            n,
+            p: if m > S::ZERO { n * d } else { Float3::zero() },
+            d,
         }
     }
+
+    /// whether `v_` lies strictly in front of the face
+    pub fn is_visible(&self, v_:&Float3<S>) -> bool {
+        self.n.dot(&(*v_ - self.v0.v)) > S::ZERO
+    }
+
+    /// whether the projection of the origin `p` falls inside the triangle
+    pub fn contains_projection(&self) -> bool {
+        let w = nearest_on_triangle(&self.v0.v, &self.v1.v, &self.v2.v);
+        let q = self.v0.v * w[0] + self.v1.v * w[1] + self.v2.v * w[2];
+        (q - self.p).sqr_magnitude() <= self.v0.v.sqr_magnitude() * S::TOLERANCE * S::TOLERANCE
+    }
 }
 
 // endregion
 
-pub fn plane_normal(v0_:&Float3, v1_:&Float3) -> Float3 {
+pub fn plane_normal<S>(v0_:&Float3<S>, v1_:&Float3<S>) -> Float3<S> where S: Scalar {
     let cross01 = Float3::cross(v0_, v1_);
     if cross01.is_zero() {
         let x = v0_.x.abs();
@@ -162,9 +352,9 @@ pub fn plane_normal(v0_:&Float3, v1_:&Float3) -> Float3 {
         let z = v0_.z.abs();
         Float3::triple_cross(
             v0_,
-            &if x < y && x < z { Float3 {x:1.0,y:0.0,z:0.0} }
-            else if y < x && y < z { Float3 {x:0.0,y:1.0,z:0.0} }
-            else { Float3 {x:0.0,y:0.0,z:1.0} },
+            &if x <= y && x <= z { Float3 {x:S::ONE,y:S::ZERO,z:S::ZERO} }
+            else if y <= z { Float3 {x:S::ZERO,y:S::ONE,z:S::ZERO} }
+            else { Float3 {x:S::ZERO,y:S::ZERO,z:S::ONE} },
             v0_,
         )
     }
@@ -173,178 +363,179 @@ pub fn plane_normal(v0_:&Float3, v1_:&Float3) -> Float3 {
     }
 }
 
-pub fn support(polya_:&[Float3], polyb_:&[Float3], d_:&Float3, f_:&mut Frame3Simplex<Float3>) -> MinkowskiSumPoint<Float3> {
-    support_in(polya_, d_, &mut f_.candidate_a);
-    support_in(polyb_, &-d_, &mut f_.candidate_b);
-    let mut min_a = 0;
-    let mut min_b = 0;
-    let mut v = Default::default();
-    let mut min = std::f32::MAX;
-    for a in &f_.candidate_a {
-        for b in &f_.candidate_b {
-            let c = polya_[*a] - polyb_[*b];
+pub fn support<T, A, B>(a_:&A, b_:&B, d_:&T, f_:&mut Frame3Simplex<T>) -> MinkowskiSumPoint<T>
+    where T: Vector, A: SupportMap<T> + ?Sized, B: SupportMap<T> + ?Sized
+{
+    a_.support_candidates_from(d_, &mut f_.start_a, &mut f_.candidate_a);
+    b_.support_candidates_from(&-*d_, &mut f_.start_b, &mut f_.candidate_b);
+    let mut ret: Point<T> = Default::default();
+    let mut min = T::Scalar::MAX;
+    for (pa, a) in &f_.candidate_a {
+        for (pb, b) in &f_.candidate_b {
+            let c = *pa - *pb;
             let n = c.sqr_magnitude();
             if n < min {
                 min = n;
-                min_a = *a;
-                min_b = *b;
-                v = c;
+                ret = MinkowskiSumPoint {v:c, pa:*pa, pb:*pb, a:*a, b:*b};
             }
         }
     }
-    println!("dir {} support {}", d_, v);
-    MinkowskiSumPoint {v:v, a:min_a, b:min_b}
-}
-
-fn support_in(poly_:&[Float3], d_:&Float3, candidate_:&mut Vec<usize>) {
-    candidate_.clear();
-    let mut max: f32 = std::f32::MIN;
-    for (i, v) in poly_.iter().enumerate() {
-        let c = v.dot(d_);
-        if c > max {
-            max = c;
-            candidate_.clear();
-        }
-        if c >= max {
-            candidate_.push(i);
-        }
-    }
+    ret
 }
 
-fn better_support_in_candidate(polya_:&[Float3], polyb_:&[Float3], n_:&Float3,
-    s1_:&mut Point<Float3>, s2_:&mut Point<Float3>, f_:&Frame3Simplex<Float3>
-) -> bool {
-    if f_.candidate_a.len() * f_.candidate_b.len() < 3 { return false; }
-    let mut s3: Point<Float3> = Default::default();
-    let mut max = 0.0;
-    for a in &f_.candidate_a {
-        for b in &f_.candidate_b {
-            let v = polya_[*a] - polyb_[*b];
-            let n = n_.dot(&(v - s1_.v));
-            if n > max {
-                max = n;
-                s3.a = *a;
-                s3.b = *b;
-                s3.v = v;
-            }
-        }
+// region nearest simplex
+
+pub(crate) fn nearest_on_segment<V>(a_:&V, b_:&V) -> [V::Scalar; 2] where V: Vector {
+    let (zero, one) = (V::Scalar::ZERO, V::Scalar::ONE);
+    let e = *b_ - *a_;
+    let d0 = -a_.dot(&e);
+    if d0 <= zero { return [one, zero] }
+    let d1 = e.sqr_magnitude();
+    if d0 >= d1 { return [zero, one] }
+    let t = d0 / d1;
+    [one - t, t]
+}
+
+/// barycentric weights of the point on triangle `a_` `b_` `c_` nearest to the origin
+pub(crate) fn nearest_on_triangle<V>(a_:&V, b_:&V, c_:&V) -> [V::Scalar; 3] where V: Vector {
+    //the weights lose precision as the angle at the first vertex closes, start from the one facing the longest edge
+    let ab = (*b_ - *a_).sqr_magnitude();
+    let bc = (*c_ - *b_).sqr_magnitude();
+    let ca = (*a_ - *c_).sqr_magnitude();
+    let zero = V::Scalar::ZERO;
+    //a repeated point leaves the segment between the other two
+    if ab == zero {
+        let w = nearest_on_segment(b_, c_);
+        return [zero, w[0], w[1]]
     }
-    if max > 0.0 {
-        std::mem::swap(s1_, s2_);
-        *s1_ = s3;
+    if bc == zero || ca == zero {
+        let w = nearest_on_segment(a_, b_);
+        return [w[0], w[1], zero]
     }
-    max > 0.0
-}
-
-macro_rules! print_candidate_permutation {
-    ($polya_:expr, $polyb_:expr, $candidate_a:expr, $candidate_b:expr) => {
-        println!("candidate:");
-        let mut distinct = Vec::new();
-        for a in $candidate_a.iter() {
-            for b in $candidate_b.iter() {
-                let v = $polya_[*a] - $polyb_[*b];
-                if distinct.contains(&v) { continue; }
-                distinct.push(v);
-                print!("{} ", v);
-            }
-        }
-        println!();
+    if bc >= ab && bc >= ca { return nearest_on_triangle_from(a_, b_, c_) }
+    if ca >= ab {
+        let w = nearest_on_triangle_from(b_, c_, a_);
+        return [w[2], w[0], w[1]]
     }
+    let w = nearest_on_triangle_from(c_, a_, b_);
+    [w[1], w[2], w[0]]
 }
 
-pub fn calculate_mtv_from_nearest_feature(polya_:&[Float3], polyb_:&[Float3], f_:&mut Frame3Simplex<Float3>, 
-    mut s0:Point<Float3>, mut s1:Point<Float3>, mut s2:Point<Float3>, d:&Float3
-) -> Result<bool, Error> {
-    println!("nearest feature");
-    let mut iteration = 0;
-    const MAX_ITERATION: i32 = 16;
-    print_candidate_permutation!(polya_, polyb_, f_.candidate_a, f_.candidate_b);
-    loop {
-        let n0 = (s1.v-s0.v).cross(d);
-        let n1 = (s2.v-s1.v).cross(d);
-        let n2 = (s0.v-s2.v).cross(d);
-        let d0 = -n0.dot(&s0.v);
-        let d1 = -n1.dot(&s1.v);
-        let d2 = -n2.dot(&s2.v);
-        println!("{} {} {}", d0, d1, d2);
-        if d0 < 0.0 && d1 < 0.0 && d2 < 0.0 {
-            f_.cache(s0, s1, s2);
-            f_.mtv_from_face_case(&d, polya_, polyb_);
-            break Ok(false)
-        }
-        else if d0 >= 0.0 && !n0.is_zero() && (d0 == 0.0 || !better_support_in_candidate(polya_, polyb_, &n0, &mut s1, &mut s2, f_)) {
-            f_.mtv_from_edge_case(&s0, &s1, polya_, polyb_);
-            f_.cache(s0, s1, s2);
-            break Ok(false)
-        }
-        else if d1 >= 0.0 && !n1.is_zero() && (d1 == 0.0 || !better_support_in_candidate(polya_, polyb_, &n1, &mut s0, &mut s2, f_)) {
-            f_.mtv_from_edge_case(&s1, &s2, polya_, polyb_);
-            f_.cache(s0, s1, s2);
-            break Ok(false)
-        }
-        else if d2 >= 0.0 && !n2.is_zero() && (d2 == 0.0 || !better_support_in_candidate(polya_, polyb_, &n2, &mut s0, &mut s1, f_)) {
-            f_.mtv_from_edge_case(&s2, &s0, polya_, polyb_);
-            f_.cache(s0, s1, s2);
-            break Ok(false)
-        }
-        //println!("dot {} {} {} {} {} {}", d0, d1, d2, s0, s1, s2);
-        iteration += 1;
-        if iteration >= MAX_ITERATION { break Err(Error::NearestFeatureSearch) }
+/// `nearest_on_triangle` with `b_` `c_` the longest edge
+fn nearest_on_triangle_from<V>(a_:&V, b_:&V, c_:&V) -> [V::Scalar; 3] where V: Vector {
+    let (zero, one) = (V::Scalar::ZERO, V::Scalar::ONE);
+    let ab = *b_ - *a_;
+    let ac = *c_ - *a_;
+    let d1 = -ab.dot(a_);
+    let d2 = -ac.dot(a_);
+    if d1 <= zero && d2 <= zero { return [one, zero, zero] }
+    let d3 = -ab.dot(b_);
+    let d4 = -ac.dot(b_);
+    if d3 >= zero && d4 <= d3 { return [zero, one, zero] }
+    let vc = d1 * d4 - d3 * d2;
+    if vc <= zero && d1 >= zero && d3 <= zero {
+        let t = d1 / (d1 - d3);
+        return [one - t, t, zero]
     }
+    let d5 = -ab.dot(c_);
+    let d6 = -ac.dot(c_);
+    if d6 >= zero && d5 <= d6 { return [zero, zero, one] }
+    let vb = d5 * d2 - d1 * d6;
+    if vb <= zero && d2 >= zero && d6 <= zero {
+        let t = d2 / (d2 - d6);
+        return [one - t, zero, t]
+    }
+    let va = d3 * d6 - d5 * d4;
+    if va <= zero && d4 - d3 >= zero && d5 - d6 >= zero {
+        let t = (d4 - d3) / ((d4 - d3) + (d5 - d6));
+        return [zero, one - t, t]
+    }
+    let denom = va + vb + vc;
+    if denom <= zero {
+        //degenerate triangle, fall back to its longest edge
+        let w = nearest_on_segment(b_, c_);
+        return [zero, w[0], w[1]]
+    }
+    [va / denom, vb / denom, vc / denom]
 }
 
-impl Frame3Simplex<Float3> {
-    fn normalize(&mut self) {
-        self.min_dist = self.mtv.magnitude();
-        if self.min_dist > 0.0 { self.mtv.scale(1.0, self.min_dist); }
-    }
+fn volume<S>(a_:&Float3<S>, b_:&Float3<S>, c_:&Float3<S>, d_:&Float3<S>) -> S where S: Scalar {
+    (*b_ - *a_).dot(&Float3::cross(&(*c_ - *a_), &(*d_ - *a_)))
+}
 
-    pub fn mtv_from_epa(&mut self, e_:&EPA2Simplex<Float3>, polya_:&[Float3], polyb_:&[Float3]) {
-        self.s0 = e_.v0.clone();
-        self.s1 = e_.v1.clone();
-        self.s2 = e_.v2.clone();
-        match e_.d2 as i32 {
-            0 => self.mtv_from_edge_case_precomputed(polya_, polyb_, &e_.v0, &e_.v1, e_.d0, e_.d1, &e_.n),
-            1 => self.mtv_from_edge_case_precomputed(polya_, polyb_, &e_.v1, &e_.v2, e_.d0, e_.d1, &e_.n),
-            2 => self.mtv_from_edge_case_precomputed(polya_, polyb_, &e_.v2, &e_.v0, e_.d0, e_.d1, &e_.n),
-            _ => self.mtv_from_face_case(&e_.p, polya_, polyb_),
+/// barycentric weights of the point on tetrahedron `p_` nearest to the origin
+fn nearest_on_tetrahedron<S>(p_:[&Float3<S>; 4]) -> [S; 4] where S: Scalar {
+    const FACES: [[usize; 4]; 4] = [[0, 1, 2, 3], [0, 3, 1, 2], [0, 2, 3, 1], [1, 3, 2, 0]];
+    let mut ret = [S::ZERO; 4];
+    let mut min = S::MAX;
+    let mut outside = false;
+    for f in FACES.iter() {
+        let (a, b, c, d) = (p_[f[0]], p_[f[1]], p_[f[2]], p_[f[3]]);
+        let n = a.triangle_normal(b, c);
+        //origin and the opposite vertex on different sides of the face
+        if n.dot(a) * n.dot(&(*d - *a)) <= S::ZERO { continue }
+        outside = true;
+        let w = nearest_on_triangle(a, b, c);
+        let v = *a * w[0] + *b * w[1] + *c * w[2];
+        let dist = v.sqr_magnitude();
+        if dist < min {
+            min = dist;
+            ret = [S::ZERO; 4];
+            ret[f[0]] = w[0];
+            ret[f[1]] = w[1];
+            ret[f[2]] = w[2];
         }
     }
+    if outside { return ret }
+    let total = volume(p_[0], p_[1], p_[2], p_[3]);
+    if total == S::ZERO { return [S::from_f32(0.25); 4] }
+    let zero = Float3::zero();
+    [
+        volume(&zero, p_[1], p_[2], p_[3]) / total,
+        volume(p_[0], &zero, p_[2], p_[3]) / total,
+        volume(p_[0], p_[1], &zero, p_[3]) / total,
+        volume(p_[0], p_[1], p_[2], &zero) / total,
+    ]
+}
 
-    fn mtv_from_edge_case_precomputed(&mut self, polya_:&[Float3], polyb_:&[Float3],
-        s0_:&Point<Float3>, s1_:&Point<Float3>, d0_:f32, d1_:f32, mtv_:&Float3
-    ) {
-        self.closest_a = Float3::lerp_clamp(&polya_[s0_.a], &polya_[s1_.a], d0_, d1_);
-        self.closest_b = Float3::lerp_clamp(&polyb_[s0_.b], &polyb_[s1_.b], d0_, d1_);
-        self.mtv = *mtv_;
-        self.normalize();
+/// Reduces `s_` (1 to 4 points) to the sub-simplex holding the point nearest to the origin,
+/// writes the barycentric weights of that point into `w_` and returns it.
+/// All 4 points are kept only when the tetrahedron encloses the origin.
+pub fn nearest_simplex<S>(s_:&mut Vec<Point<Float3<S>>>, w_:&mut Vec<S>) -> Float3<S> where S: Scalar {
+    let w: Vec<S> = match s_.len() {
+        1 => vec![S::ONE],
+        2 => nearest_on_segment(&s_[0].v, &s_[1].v).to_vec(),
+        3 => nearest_on_triangle(&s_[0].v, &s_[1].v, &s_[2].v).to_vec(),
+        _ => nearest_on_tetrahedron([&s_[0].v, &s_[1].v, &s_[2].v, &s_[3].v]).to_vec(),
+    };
+    w_.clear();
+    let mut i = 0;
+    s_.retain(|_| { i += 1; w[i - 1] > S::ZERO });
+    w_.extend(w.iter().filter(|w| **w > S::ZERO));
+    let mut v = Float3::zero();
+    for (p, w) in s_.iter().zip(w_.iter()) {
+        v = v + p.v * *w;
     }
+    v
+}
 
-    fn mtv_from_edge_case(&mut self, s0_:&Point<Float3>, s1_:&Point<Float3>, polya_:&[Float3], polyb_:&[Float3]) {
-        let e = s0_.v - s1_.v;
-        let d0 = s0_.v.dot(&e);
-        let d1 = e.sqr_magnitude();
-        self.closest_a = Float3::lerp_clamp(&polya_[s0_.a], &polya_[s1_.a], d0, d1);
-        self.closest_b = Float3::lerp_clamp(&polyb_[s0_.b], &polyb_[s1_.b], d0, d1);
-        self.mtv = self.closest_a - self.closest_b;
-        self.normalize();
-    }
+/// Whether the point `v_` of the simplex `s_` is the origin up to rounding.
+pub fn encloses_origin<T>(s_:&[Point<T>], v_:&T) -> bool where T: Vector {
+    let scale = s_.iter().map(|p| p.v.sqr_magnitude()).fold(T::Scalar::ZERO, |a, b| a.max(b));
+    v_.sqr_magnitude() <= scale * T::Scalar::EPSILON
+}
+
+// endregion
 
-    fn mtv_from_face_case(&mut self, n_:&Float3, polya_:&[Float3], polyb_:&[Float3]) {
-        self.mtv = *n_;
-        let mut e0 = self.s1.v - self.s0.v;
-        let n0 = Float3::cross(&n_, &(*n_-self.s2.v));
-        let d0 = n0.dot(&(self.s0.v-self.s2.v));
-        let d1 = -n0.dot(&e0);
-        let e1 = Float3::lerp(&polya_[self.s0.a], &polya_[self.s1.a], d0, d1);
-        let e2 = Float3::lerp(&polyb_[self.s0.b], &polyb_[self.s1.b], d0, d1);
-        e0.scale(d0, d1);
-        let p = self.s0.v + e0;
-        let n0 = p - self.s2.v;
-        let d0 = p.dot(&n0);
-        let d1 = n0.sqr_magnitude();
-        self.closest_a = Float3::lerp(&e1, &polya_[self.s2.a], d0, d1);
-        self.closest_b = Float3::lerp(&e2, &polya_[self.s2.b], d0, d1);
-        self.normalize();
+impl<S> CollisionResult<Float3<S>> where S: Scalar {
+    /// builds the result of EPA from the face of the polytope nearest to the origin, see `converged`
+    pub fn from_epa(e_:&EPA2Simplex<Float3<S>>, simplex_:Vec<Point<Float3<S>>>, iteration_:usize, converged_:bool) -> CollisionResult<Float3<S>> {
+        let w = nearest_on_triangle(&e_.v0.v, &e_.v1.v, &e_.v2.v);
+        let scale = e_.v0.v.magnitude().max(e_.v1.v.magnitude()).max(e_.v2.v.magnitude());
+        CollisionResult::from_penetration(
+            e_.v0.pa * w[0] + e_.v1.pa * w[1] + e_.v2.pa * w[2],
+            e_.v0.pb * w[0] + e_.v1.pb * w[1] + e_.v2.pb * w[2],
+            e_.n, e_.d, scale, simplex_, iteration_, converged_,
+        )
     }
-}
\ No newline at end of file
+}
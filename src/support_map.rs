@@ -0,0 +1,131 @@
+use crate::vector::{Float3, Scalar, Vector};
+
+/// Identifies the feature of a shape a support point was taken from.
+/// For vertex clouds this is the vertex index; implicit shapes use their own numbering.
+pub type FeatureId = usize;
+
+/// How far a point may fall behind the support plane and still belong to the face `support_face` reports,
+/// relative to the extent of the shape along the query direction.
+pub const FACE_TOLERANCE: f32 = 1e-3;
+
+/// A convex shape described by its support function.
+///
+/// The GJK, nearest simplex and EPA searches only ever ask a shape for its furthest
+/// point along a direction, so anything implementing this trait can be queried.
+pub trait SupportMap<V = Float3> where V: Vector {
+    /// Furthest point of the shape along `d_`, together with the feature it belongs to.
+    fn support(&self, d_:&V) -> (V, FeatureId);
+
+    /// Collects every point tied for the furthest along `d_` into `candidate_`.
+    /// Shapes with flat faces can report all of them to help the nearest feature search;
+    /// the default only reports the single point from `support`.
+    fn support_candidates(&self, d_:&V, candidate_:&mut Vec<(V, FeatureId)>) {
+        candidate_.clear();
+        candidate_.push(self.support(d_));
+    }
+
+    /// Same as `support_candidates`, for a query that keeps its own start: `start_` holds the feature
+    /// the last query through it ended on, `None` at first, and is left on the one this query ends on.
+    /// Shapes that walk their surface towards the support point start there; the default ignores it.
+    fn support_candidates_from(&self, d_:&V, start_:&mut Option<FeatureId>, candidate_:&mut Vec<(V, FeatureId)>) {
+        let _ = start_;
+        self.support_candidates(d_, candidate_)
+    }
+
+    /// Collects the points of the face most aligned with `d_` into `face_`, in no particular order:
+    /// every vertex within `FACE_TOLERANCE` of the support plane, so a face only roughly facing `d_` is still reported whole.
+    /// Curved shapes have no faces, the default only reports the points `support_candidates` does.
+    fn support_face(&self, d_:&V, face_:&mut Vec<(V, FeatureId)>) {
+        self.support_candidates(d_, face_)
+    }
+
+    /// Position of the vertex `id_` as reported by `support`, for shapes whose features are fixed points.
+    /// Lets a query re-evaluate a cached simplex after the shape moved; the default has no such points.
+    fn feature_point(&self, _id_:FeatureId) -> Option<V> { None }
+
+    /// Whether the shape can be queried at all.
+    fn is_valid(&self) -> bool { true }
+}
+
+impl<V> SupportMap<V> for [V] where V: Vector {
+    fn support(&self, d_:&V) -> (V, FeatureId) {
+        let mut max = V::Scalar::MIN;
+        let mut ret = 0;
+        for (i, v) in self.iter().enumerate() {
+            let c = v.dot(d_);
+            if c > max {
+                max = c;
+                ret = i;
+            }
+        }
+        (self[ret], ret)
+    }
+
+    fn support_candidates(&self, d_:&V, candidate_:&mut Vec<(V, FeatureId)>) {
+        candidate_.clear();
+        let mut max = V::Scalar::MIN;
+        for (i, v) in self.iter().enumerate() {
+            let c = v.dot(d_);
+            if c > max {
+                max = c;
+                candidate_.clear();
+            }
+            if c >= max {
+                candidate_.push((*v, i));
+            }
+        }
+    }
+
+    fn support_face(&self, d_:&V, face_:&mut Vec<(V, FeatureId)>) {
+        face_.clear();
+        let (mut max, mut min) = (V::Scalar::MIN, V::Scalar::MAX);
+        for v in self.iter() {
+            let c = v.dot(d_);
+            max = max.max(c);
+            min = min.min(c);
+        }
+        let limit = max - (max - min) * V::Scalar::from_f32(FACE_TOLERANCE);
+        for (i, v) in self.iter().enumerate() {
+            if v.dot(d_) >= limit {
+                face_.push((*v, i));
+            }
+        }
+    }
+
+    fn feature_point(&self, id_:FeatureId) -> Option<V> { self.get(id_).copied() }
+
+    /// A vertex cloud needs at least `DIMENSION + 1` points to span the space.
+    fn is_valid(&self) -> bool { self.len() > V::DIMENSION }
+}
+
+impl<V> SupportMap<V> for Vec<V> where V: Vector {
+    fn support(&self, d_:&V) -> (V, FeatureId) { self.as_slice().support(d_) }
+
+    fn support_candidates(&self, d_:&V, candidate_:&mut Vec<(V, FeatureId)>) {
+        self.as_slice().support_candidates(d_, candidate_)
+    }
+
+    fn support_face(&self, d_:&V, face_:&mut Vec<(V, FeatureId)>) { self.as_slice().support_face(d_, face_) }
+
+    fn feature_point(&self, id_:FeatureId) -> Option<V> { self.as_slice().feature_point(id_) }
+
+    fn is_valid(&self) -> bool { self.as_slice().is_valid() }
+}
+
+impl<V, S> SupportMap<V> for &S where V: Vector, S: SupportMap<V> + ?Sized {
+    fn support(&self, d_:&V) -> (V, FeatureId) { (**self).support(d_) }
+
+    fn support_candidates(&self, d_:&V, candidate_:&mut Vec<(V, FeatureId)>) {
+        (**self).support_candidates(d_, candidate_)
+    }
+
+    fn support_candidates_from(&self, d_:&V, start_:&mut Option<FeatureId>, candidate_:&mut Vec<(V, FeatureId)>) {
+        (**self).support_candidates_from(d_, start_, candidate_)
+    }
+
+    fn support_face(&self, d_:&V, face_:&mut Vec<(V, FeatureId)>) { (**self).support_face(d_, face_) }
+
+    fn feature_point(&self, id_:FeatureId) -> Option<V> { (**self).feature_point(id_) }
+
+    fn is_valid(&self) -> bool { (**self).is_valid() }
+}
@@ -1,41 +1,108 @@
+use std::ops::{Add, Div, Mul, Neg, Sub};
+
+/// Floating point type the vectors and the solver are generic over.
+pub trait Scalar:
+    Copy + PartialOrd + Default + std::fmt::Debug + std::fmt::Display
+    + Add<Output = Self> + Sub<Output = Self> + Mul<Output = Self> + Div<Output = Self> + Neg<Output = Self>
+{
+    const ZERO: Self;
+    const ONE: Self;
+    const MAX: Self;
+    const MIN: Self;
+    const EPSILON: Self;
+    /// relative precision the iterative searches settle for
+    const TOLERANCE: Self;
+
+    fn from_f32(v_:f32) -> Self;
+    fn from_f64(v_:f64) -> Self;
+    fn to_f64(self) -> f64;
+    fn sqrt(self) -> Self;
+    fn abs(self) -> Self;
+    fn sin_cos(self) -> (Self, Self);
+    fn max(self, b_:Self) -> Self { if self >= b_ { self } else { b_ } }
+    fn min(self, b_:Self) -> Self { if self <= b_ { self } else { b_ } }
+    fn clamp(self, min_:Self, max_:Self) -> Self { self.max(min_).min(max_) }
+}
+
+macro_rules! impl_scalar {
+    ($t:ident, $tolerance:expr) => {
+        impl Scalar for $t {
+            const ZERO: $t = 0.0;
+            const ONE: $t = 1.0;
+            const MAX: $t = $t::MAX;
+            const MIN: $t = $t::MIN;
+            const EPSILON: $t = $t::EPSILON;
+            const TOLERANCE: $t = $tolerance;
+
+            fn from_f32(v_:f32) -> $t { v_ as $t }
+            fn from_f64(v_:f64) -> $t { v_ as $t }
+            fn to_f64(self) -> f64 { self as f64 }
+            fn sqrt(self) -> $t { $t::sqrt(self) }
+            fn abs(self) -> $t { $t::abs(self) }
+            fn sin_cos(self) -> ($t, $t) { $t::sin_cos(self) }
+        }
+    }
+}
+
+impl_scalar!(f32, 1e-5);
+impl_scalar!(f64, 1e-10);
+
+/// Operations the dimension independent parts of the solver need from a vector type.
+pub trait Vector:
+    Copy + Default + PartialEq + std::fmt::Debug + std::fmt::Display
+    + Add<Output = Self> + Sub<Output = Self> + Neg<Output = Self> + Mul<<Self as Vector>::Scalar, Output = Self>
+{
+    type Scalar: Scalar;
+    /// number of components, a simplex spanning the space has `DIMENSION + 1` points
+    const DIMENSION: usize;
+
+    fn dot(&self, b:&Self) -> Self::Scalar;
+
+    fn sqr_magnitude(&self) -> Self::Scalar { self.dot(self) }
+}
+
 #[derive(Copy, Clone)]
 #[derive(PartialEq)]
 #[derive(Debug)]
-pub struct Float3 {
-    pub x: f32,
-    pub y: f32,
-    pub z: f32,
+pub struct Float3<S = f32> {
+    pub x: S,
+    pub y: S,
+    pub z: S,
 }
 
-impl Float3 {
-    pub fn zero() -> Float3 {
-        Float3 {x:0.0, y:0.0, z:0.0}
+impl<S> Float3<S> where S: Scalar {
+    pub fn new(x_:S, y_:S, z_:S) -> Float3<S> {
+        Float3 {x:x_, y:y_, z:z_}
+    }
+
+    pub fn zero() -> Float3<S> {
+        Float3 {x:S::ZERO, y:S::ZERO, z:S::ZERO}
     }
 
     pub fn is_zero(&self) -> bool {
-        self.x == 0.0 && self.y == 0.0
+        self.x == S::ZERO && self.y == S::ZERO && self.z == S::ZERO
     }
 
-    pub fn sqr_magnitude(&self) -> f32 {
+    pub fn sqr_magnitude(&self) -> S {
         self.x * self.x + self.y * self.y + self.z * self.z
     }
 
-    pub fn magnitude(&self) -> f32 {
+    pub fn magnitude(&self) -> S {
         (self.x * self.x + self.y * self.y + self.z * self.z).sqrt()
     }
 
     pub fn normalize(&mut self) {
         let m = self.magnitude();
-        self.x /= m;
-        self.y /= m;
-        self.z /= m;
+        self.x = self.x / m;
+        self.y = self.y / m;
+        self.z = self.z / m;
     }
 
-    pub fn dot(&self, b:&Float3) -> f32 {
+    pub fn dot(&self, b:&Float3<S>) -> S {
         self.x * b.x + self.y * b.y + self.z * b.z
     }
 
-    pub fn cross(&self, b:&Float3) -> Float3 {
+    pub fn cross(&self, b:&Float3<S>) -> Float3<S> {
         Float3 {
             x: self.y * b.z - self.z * b.y,
             y: self.z * b.x - self.x * b.z,
@@ -43,25 +110,24 @@ impl Float3 {
         }
     }
 
-    pub fn triangle_normal(&self, b:&Float3, c:&Float3) -> Float3 {
+    pub fn triangle_normal(&self, b:&Float3<S>, c:&Float3<S>) -> Float3<S> {
         let l0 = *b - *self;
         let l1 = *c - *self;
-        println!("{} {} {} => {} {}", self, b, c, l0, l1);
         Float3::cross(&l0, &l1)
     }
 
-    pub fn triple_cross(&self, b:&Float3, c:&Float3) -> Float3 {
+    pub fn triple_cross(&self, b:&Float3<S>, c:&Float3<S>) -> Float3<S> {
         Float3::cross(&Float3::cross(self, b), c)
     }
 
-    pub fn scale(&mut self, p_:f32, b_:f32) {
+    pub fn scale(&mut self, p_:S, b_:S) {
         let f = p_ / b_;
-        self.x *= f;
-        self.y *= f;
-        self.z *= f;
+        self.x = self.x * f;
+        self.y = self.y * f;
+        self.z = self.z * f;
     }
 
-    pub fn scaled(&self, p_:f32, b_:f32) -> Float3 {
+    pub fn scaled(&self, p_:S, b_:S) -> Float3<S> {
         let f = p_ / b_;
         Float3 {
             x: self.x * f,
@@ -70,9 +136,9 @@ impl Float3 {
         }
     }
 
-    pub fn lerp(&self, b:&Float3, p_:f32, t_:f32) -> Float3 {
+    pub fn lerp(&self, b:&Float3<S>, p_:S, t_:S) -> Float3<S> {
         let f = p_ / t_;
-        let k = 1.0-f;
+        let k = S::ONE - f;
         Float3 {
             x:self.x * k + b.x * f,
             y:self.y * k + b.y * f,
@@ -80,10 +146,10 @@ impl Float3 {
         }
     }
 
-    pub fn lerp_clamp(&self, b:&Float3, p_:f32, t_:f32) -> Float3 {
+    pub fn lerp_clamp(&self, b:&Float3<S>, p_:S, t_:S) -> Float3<S> {
         let f = p_ / t_;
-        let f = if f < 0.0 { 0.0 } else if f > 1.0 { 1.0 } else { f };
-        let k = 1.0-f;
+        let f = f.clamp(S::ZERO, S::ONE);
+        let k = S::ONE - f;
         Float3 {
             x:self.x * k + b.x * f,
             y:self.y * k + b.y * f,
@@ -92,20 +158,27 @@ impl Float3 {
     }
 }
 
-impl std::fmt::Display for Float3 {
+impl<S> Vector for Float3<S> where S: Scalar {
+    type Scalar = S;
+    const DIMENSION: usize = 3;
+
+    fn dot(&self, b:&Float3<S>) -> S { Float3::dot(self, b) }
+}
+
+impl<S> std::fmt::Display for Float3<S> where S: std::fmt::Display {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(f, "({},{},{})", self.x, self.y, self.z)
     }
 }
 
-impl Default for Float3 {
+impl<S> Default for Float3<S> where S: Scalar {
     fn default() -> Self { Float3::zero() }
 }
 
-impl std::ops::Add for Float3 {
-    type Output = Float3;
+impl<S> Add for Float3<S> where S: Scalar {
+    type Output = Float3<S>;
 
-    fn add(self, rhs_: Float3) -> Float3 {
+    fn add(self, rhs_: Float3<S>) -> Float3<S> {
         Float3 {
             x: self.x + rhs_.x,
             y: self.y + rhs_.y,
@@ -114,10 +187,10 @@ impl std::ops::Add for Float3 {
     }
 }
 
-impl std::ops::Sub for Float3 {
-    type Output = Float3;
+impl<S> Sub for Float3<S> where S: Scalar {
+    type Output = Float3<S>;
 
-    fn sub(self, rhs_: Float3) -> Float3 {
+    fn sub(self, rhs_: Float3<S>) -> Float3<S> {
         Float3 {
             x: self.x - rhs_.x,
             y: self.y - rhs_.y,
@@ -126,18 +199,447 @@ impl std::ops::Sub for Float3 {
     }
 }
 
-impl std::ops::Neg for Float3 {
-    type Output = Float3;
+impl<S> Mul<S> for Float3<S> where S: Scalar {
+    type Output = Float3<S>;
 
-    fn neg(self) -> Float3 {
+    fn mul(self, rhs_: S) -> Float3<S> {
+        Float3 {
+            x: self.x * rhs_,
+            y: self.y * rhs_,
+            z: self.z * rhs_,
+        }
+    }
+}
+
+impl<S> Neg for Float3<S> where S: Scalar {
+    type Output = Float3<S>;
+
+    fn neg(self) -> Float3<S> {
         Float3 {x:-self.x, y:-self.y, z:-self.z}
     }
 }
 
-impl std::ops::Neg for &Float3 {
-    type Output = Float3;
+impl<S> Neg for &Float3<S> where S: Scalar {
+    type Output = Float3<S>;
 
-    fn neg(self) -> Float3 {
+    fn neg(self) -> Float3<S> {
         Float3 {x:-self.x, y:-self.y, z:-self.z}
     }
-}
\ No newline at end of file
+}
+
+// region Float2
+
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub struct Float2<S = f32> {
+    pub x: S,
+    pub y: S,
+}
+
+impl<S> Float2<S> where S: Scalar {
+    pub fn new(x_:S, y_:S) -> Float2<S> {
+        Float2 {x:x_, y:y_}
+    }
+
+    pub fn zero() -> Float2<S> {
+        Float2 {x:S::ZERO, y:S::ZERO}
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.x == S::ZERO && self.y == S::ZERO
+    }
+
+    pub fn sqr_magnitude(&self) -> S {
+        self.x * self.x + self.y * self.y
+    }
+
+    pub fn magnitude(&self) -> S {
+        (self.x * self.x + self.y * self.y).sqrt()
+    }
+
+    pub fn normalize(&mut self) {
+        let m = self.magnitude();
+        self.x = self.x / m;
+        self.y = self.y / m;
+    }
+
+    pub fn dot(&self, b:&Float2<S>) -> S {
+        self.x * b.x + self.y * b.y
+    }
+
+    /// z component of the 3D cross product, positive when `b` is counter clockwise from `self`
+    pub fn cross(&self, b:&Float2<S>) -> S {
+        self.x * b.y - self.y * b.x
+    }
+
+    /// `self` rotated a quarter turn clockwise
+    pub fn perp(&self) -> Float2<S> {
+        Float2 {x:self.y, y:-self.x}
+    }
+
+    pub fn scaled(&self, p_:S, b_:S) -> Float2<S> {
+        let f = p_ / b_;
+        Float2 {
+            x: self.x * f,
+            y: self.y * f,
+        }
+    }
+}
+
+impl<S> Vector for Float2<S> where S: Scalar {
+    type Scalar = S;
+    const DIMENSION: usize = 2;
+
+    fn dot(&self, b:&Float2<S>) -> S { Float2::dot(self, b) }
+}
+
+impl<S> std::fmt::Display for Float2<S> where S: std::fmt::Display {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({},{})", self.x, self.y)
+    }
+}
+
+impl<S> Default for Float2<S> where S: Scalar {
+    fn default() -> Self { Float2::zero() }
+}
+
+impl<S> Add for Float2<S> where S: Scalar {
+    type Output = Float2<S>;
+
+    fn add(self, rhs_: Float2<S>) -> Float2<S> {
+        Float2 {x:self.x + rhs_.x, y:self.y + rhs_.y}
+    }
+}
+
+impl<S> Sub for Float2<S> where S: Scalar {
+    type Output = Float2<S>;
+
+    fn sub(self, rhs_: Float2<S>) -> Float2<S> {
+        Float2 {x:self.x - rhs_.x, y:self.y - rhs_.y}
+    }
+}
+
+impl<S> Mul<S> for Float2<S> where S: Scalar {
+    type Output = Float2<S>;
+
+    fn mul(self, rhs_: S) -> Float2<S> {
+        Float2 {x:self.x * rhs_, y:self.y * rhs_}
+    }
+}
+
+impl<S> Neg for Float2<S> where S: Scalar {
+    type Output = Float2<S>;
+
+    fn neg(self) -> Float2<S> {
+        Float2 {x:-self.x, y:-self.y}
+    }
+}
+
+impl<S> Neg for &Float2<S> where S: Scalar {
+    type Output = Float2<S>;
+
+    fn neg(self) -> Float2<S> {
+        Float2 {x:-self.x, y:-self.y}
+    }
+}
+
+// endregion
+
+// region Quat
+
+/// Rotation quaternion, `w` is the scalar part.
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub struct Quat<S = f32> {
+    pub x: S,
+    pub y: S,
+    pub z: S,
+    pub w: S,
+}
+
+impl<S> Quat<S> where S: Scalar {
+    pub fn identity() -> Quat<S> {
+        Quat {x:S::ZERO, y:S::ZERO, z:S::ZERO, w:S::ONE}
+    }
+
+    /// rotation of `angle_` radians around `axis_`, which does not need to be normalized
+    pub fn from_axis_angle(axis_:&Float3<S>, angle_:S) -> Quat<S> {
+        let m = axis_.magnitude();
+        if m == S::ZERO { return Quat::identity() }
+        let (s, c) = (angle_ * S::from_f32(0.5)).sin_cos();
+        let a = axis_.scaled(s, m);
+        Quat {x:a.x, y:a.y, z:a.z, w:c}
+    }
+
+    /// rotation by the angle `|v_|` around `v_`, as produced by integrating an angular velocity
+    pub fn from_scaled_axis(v_:&Float3<S>) -> Quat<S> {
+        Quat::from_axis_angle(v_, v_.magnitude())
+    }
+
+    pub fn magnitude(&self) -> S {
+        (self.x * self.x + self.y * self.y + self.z * self.z + self.w * self.w).sqrt()
+    }
+
+    pub fn normalize(&mut self) {
+        let m = self.magnitude();
+        self.x = self.x / m;
+        self.y = self.y / m;
+        self.z = self.z / m;
+        self.w = self.w / m;
+    }
+
+    pub fn conjugate(&self) -> Quat<S> {
+        Quat {x:-self.x, y:-self.y, z:-self.z, w:self.w}
+    }
+
+    pub fn rotate(&self, v_:&Float3<S>) -> Float3<S> {
+        let q = Float3 {x:self.x, y:self.y, z:self.z};
+        let t = Float3::cross(&q, v_) * (S::ONE + S::ONE);
+        *v_ + t * self.w + Float3::cross(&q, &t)
+    }
+
+    pub fn inverse_rotate(&self, v_:&Float3<S>) -> Float3<S> {
+        self.conjugate().rotate(v_)
+    }
+}
+
+impl<S> Default for Quat<S> where S: Scalar {
+    fn default() -> Self { Quat::identity() }
+}
+
+impl<S> std::fmt::Display for Quat<S> where S: std::fmt::Display {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "({},{},{},{})", self.x, self.y, self.z, self.w)
+    }
+}
+
+impl<S> Mul for Quat<S> where S: Scalar {
+    type Output = Quat<S>;
+
+    /// `self` applied after `rhs_`
+    fn mul(self, rhs_: Quat<S>) -> Quat<S> {
+        Quat {
+            x: self.w * rhs_.x + self.x * rhs_.w + self.y * rhs_.z - self.z * rhs_.y,
+            y: self.w * rhs_.y - self.x * rhs_.z + self.y * rhs_.w + self.z * rhs_.x,
+            z: self.w * rhs_.z + self.x * rhs_.y - self.y * rhs_.x + self.z * rhs_.w,
+            w: self.w * rhs_.w - self.x * rhs_.x - self.y * rhs_.y - self.z * rhs_.z,
+        }
+    }
+}
+
+// endregion
+
+// region Mat3
+
+/// Row major 3x3 matrix.
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub struct Mat3<S = f32> {
+    pub r0: Float3<S>,
+    pub r1: Float3<S>,
+    pub r2: Float3<S>,
+}
+
+impl<S> Mat3<S> where S: Scalar {
+    pub fn identity() -> Mat3<S> {
+        Mat3 {
+            r0: Float3 {x:S::ONE, y:S::ZERO, z:S::ZERO},
+            r1: Float3 {x:S::ZERO, y:S::ONE, z:S::ZERO},
+            r2: Float3 {x:S::ZERO, y:S::ZERO, z:S::ONE},
+        }
+    }
+
+    /// rotation matrix of the unit quaternion `q_`
+    pub fn from_quat(q_:&Quat<S>) -> Mat3<S> {
+        let (x, y, z, w) = (q_.x, q_.y, q_.z, q_.w);
+        let one = S::ONE;
+        let two = one + one;
+        Mat3 {
+            r0: Float3 {x:one - two * (y * y + z * z), y:two * (x * y - z * w), z:two * (x * z + y * w)},
+            r1: Float3 {x:two * (x * y + z * w), y:one - two * (x * x + z * z), z:two * (y * z - x * w)},
+            r2: Float3 {x:two * (x * z - y * w), y:two * (y * z + x * w), z:one - two * (x * x + y * y)},
+        }
+    }
+
+    pub fn column(&self, i_:usize) -> Float3<S> {
+        match i_ {
+            0 => Float3 {x:self.r0.x, y:self.r1.x, z:self.r2.x},
+            1 => Float3 {x:self.r0.y, y:self.r1.y, z:self.r2.y},
+            _ => Float3 {x:self.r0.z, y:self.r1.z, z:self.r2.z},
+        }
+    }
+
+    pub fn transpose(&self) -> Mat3<S> {
+        Mat3 {r0:self.column(0), r1:self.column(1), r2:self.column(2)}
+    }
+
+    pub fn mul_vec(&self, v_:&Float3<S>) -> Float3<S> {
+        Float3 {x:self.r0.dot(v_), y:self.r1.dot(v_), z:self.r2.dot(v_)}
+    }
+
+    /// `transpose() * v_`, the inverse rotation for orthonormal matrices
+    pub fn transpose_mul_vec(&self, v_:&Float3<S>) -> Float3<S> {
+        self.r0 * v_.x + self.r1 * v_.y + self.r2 * v_.z
+    }
+}
+
+impl<S> Default for Mat3<S> where S: Scalar {
+    fn default() -> Self { Mat3::identity() }
+}
+
+impl<S> Mul for Mat3<S> where S: Scalar {
+    type Output = Mat3<S>;
+
+    fn mul(self, rhs_: Mat3<S>) -> Mat3<S> {
+        let t = rhs_.transpose();
+        Mat3 {
+            r0: Float3 {x:self.r0.dot(&t.r0), y:self.r0.dot(&t.r1), z:self.r0.dot(&t.r2)},
+            r1: Float3 {x:self.r1.dot(&t.r0), y:self.r1.dot(&t.r1), z:self.r1.dot(&t.r2)},
+            r2: Float3 {x:self.r2.dot(&t.r0), y:self.r2.dot(&t.r1), z:self.r2.dot(&t.r2)},
+        }
+    }
+}
+
+// endregion
+
+// region Isometry
+
+/// Rigid transform, rotation followed by translation.
+#[derive(Copy, Clone)]
+#[derive(PartialEq)]
+#[derive(Debug)]
+pub struct Isometry<S = f32> {
+    pub rotation: Quat<S>,
+    pub translation: Float3<S>,
+}
+
+impl<S> Isometry<S> where S: Scalar {
+    pub fn identity() -> Isometry<S> {
+        Isometry {rotation:Quat::identity(), translation:Float3::zero()}
+    }
+
+    pub fn new(translation_:Float3<S>, rotation_:Quat<S>) -> Isometry<S> {
+        Isometry {rotation:rotation_, translation:translation_}
+    }
+
+    pub fn from_translation(translation_:Float3<S>) -> Isometry<S> {
+        Isometry {rotation:Quat::identity(), translation:translation_}
+    }
+
+    pub fn transform_point(&self, p_:&Float3<S>) -> Float3<S> {
+        self.rotation.rotate(p_) + self.translation
+    }
+
+    pub fn transform_vector(&self, v_:&Float3<S>) -> Float3<S> {
+        self.rotation.rotate(v_)
+    }
+
+    pub fn inverse_transform_point(&self, p_:&Float3<S>) -> Float3<S> {
+        self.rotation.inverse_rotate(&(*p_ - self.translation))
+    }
+
+    pub fn inverse_transform_vector(&self, v_:&Float3<S>) -> Float3<S> {
+        self.rotation.inverse_rotate(v_)
+    }
+
+    pub fn inverse(&self) -> Isometry<S> {
+        let r = self.rotation.conjugate();
+        Isometry {rotation:r, translation:-r.rotate(&self.translation)}
+    }
+}
+
+impl<S> Default for Isometry<S> where S: Scalar {
+    fn default() -> Self { Isometry::identity() }
+}
+
+impl<S> Mul for Isometry<S> where S: Scalar {
+    type Output = Isometry<S>;
+
+    /// `self` applied after `rhs_`
+    fn mul(self, rhs_: Isometry<S>) -> Isometry<S> {
+        Isometry {
+            rotation: self.rotation * rhs_.rotation,
+            translation: self.transform_point(&rhs_.translation),
+        }
+    }
+}
+
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self) -> f32 {
+            self.0 = self.0.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            (self.0 >> 40) as f32 / (1u64 << 24) as f32
+        }
+
+        /// point in the cube `[-scale_, scale_]^3`
+        fn point(&mut self, scale_:f32) -> Float3 {
+            Float3 {x:(self.next() * 2.0 - 1.0) * scale_, y:(self.next() * 2.0 - 1.0) * scale_, z:(self.next() * 2.0 - 1.0) * scale_}
+        }
+
+        fn quat(&mut self) -> Quat {
+            Quat::from_axis_angle(&self.point(1.0), self.next() * std::f32::consts::TAU)
+        }
+
+        fn isometry(&mut self) -> Isometry {
+            Isometry::new(self.point(10.0), self.quat())
+        }
+    }
+
+    fn assert_near(a_:Float3, b_:Float3, tolerance_:f32) {
+        assert!((a_ - b_).magnitude() <= tolerance_, "{} instead of {}", a_, b_);
+    }
+
+    #[test]
+    fn inverse_rotate_undoes_rotate() {
+        let mut rng = Lcg(3);
+        for _ in 0..100 {
+            let (q, v) = (rng.quat(), rng.point(10.0));
+            assert!((q.magnitude() - 1.0).abs() <= 1e-5);
+            assert!((q.rotate(&v).magnitude() - v.magnitude()).abs() <= 1e-4);
+            assert_near(q.inverse_rotate(&q.rotate(&v)), v, 1e-4);
+            assert_near(q.rotate(&q.inverse_rotate(&v)), v, 1e-4);
+        }
+        //a quarter turn around z takes x to y
+        let q = Quat::from_axis_angle(&Float3 {x:0.0, y:0.0, z:2.0}, std::f32::consts::FRAC_PI_2);
+        assert_near(q.rotate(&Float3 {x:1.0, y:0.0, z:0.0}), Float3 {x:0.0, y:1.0, z:0.0}, 1e-6);
+    }
+
+    #[test]
+    fn matrix_matches_quaternion() {
+        let mut rng = Lcg(5);
+        for _ in 0..100 {
+            let (q, v) = (rng.quat(), rng.point(10.0));
+            let m = Mat3::from_quat(&q);
+            assert_near(m.mul_vec(&v), q.rotate(&v), 1e-4);
+            assert_near(m.transpose_mul_vec(&v), q.inverse_rotate(&v), 1e-4);
+            //products of quaternions and of their matrices rotate alike
+            let p = rng.quat();
+            assert_near((Mat3::from_quat(&p) * m).mul_vec(&v), (p * q).rotate(&v), 1e-4);
+        }
+    }
+
+    #[test]
+    fn isometry_composition_and_inverse() {
+        let mut rng = Lcg(7);
+        for _ in 0..100 {
+            let (a, b, p) = (rng.isometry(), rng.isometry(), rng.point(10.0));
+            assert_near((a * b).transform_point(&p), a.transform_point(&b.transform_point(&p)), 1e-3);
+            assert_near(a.inverse().transform_point(&p), a.inverse_transform_point(&p), 1e-3);
+            assert_near(a.inverse_transform_vector(&a.transform_vector(&p)), p, 1e-4);
+            let identity = a * a.inverse();
+            assert_near(identity.transform_point(&p), p, 1e-3);
+            assert_near((a.inverse() * a).transform_point(&p), p, 1e-3);
+            assert_near(identity.translation, Float3::zero(), 1e-3);
+        }
+    }
+}
@@ -0,0 +1,229 @@
+use crate::vector::{Float3, Isometry, Scalar};
+use crate::simplex_based_cd_helper::{CoherentCache, CollisionResult, Error};
+use crate::support_map::SupportMap;
+use crate::shape::Posed;
+use crate::aabb::Aabb;
+use crate::broadphase::{Broadphase, DynamicTree};
+use crate::ccd::Motion;
+use crate::gjk3d;
+
+// region Object
+
+/// Refers to an object of a `World`. Stays valid until the object is removed and never refers to another object after.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ObjectHandle {
+    index: usize,
+    generation: u32,
+}
+
+impl ObjectHandle {
+    /// slot of the object, reused by objects added after it is removed
+    pub fn index(&self) -> usize { self.index }
+}
+
+/// Shape in its local space, placed by `pose` and moved by `step` at constant linear and angular velocity.
+pub struct Object<S = f32> where S: Scalar {
+    pub shape: Box<dyn SupportMap<Float3<S>>>,
+    pub pose: Isometry<S>,
+    pub linear: Float3<S>,
+    /// world space, around the origin of the local space
+    pub angular: Float3<S>,
+}
+
+impl<S> Object<S> where S: Scalar {
+    /// object at rest at `pose_`
+    pub fn new<T>(shape_:T, pose_:Isometry<S>) -> Object<S> where T: SupportMap<Float3<S>> + 'static {
+        Object {shape:Box::new(shape_), pose:pose_, linear:Float3::zero(), angular:Float3::zero()}
+    }
+
+    fn posed(&self) -> Posed<&dyn SupportMap<Float3<S>>, S> {
+        Posed::new(self.shape.as_ref(), self.pose)
+    }
+}
+
+/// Colliding pair found by `World::detect`, with `result` oriented from `a` to `b`.
+#[derive(Debug, Clone)]
+pub struct Collision<S = f32> where S: Scalar {
+    pub a: ObjectHandle,
+    pub b: ObjectHandle,
+    pub result: CollisionResult<Float3<S>>,
+}
+
+// endregion
+
+// region World
+
+struct Slot<S> where S: Scalar {
+    generation: u32,
+    object: Option<Object<S>>,
+}
+
+/// Owns the objects of a scene, culls their pairs with a broadphase
+/// and keeps a `CoherentFrame` per candidate pair, so queries warm start from the previous frame.
+pub struct World<S = f32> where S: Scalar {
+    slot: Vec<Slot<S>>,
+    free: Vec<usize>,
+    broadphase: Box<dyn Broadphase<S>>,
+    /// frames keyed by pairs of slot indices, smaller first
+    cache: CoherentCache<Float3<S>>,
+    aabb: Vec<Aabb<S>>,
+    pair: Vec<(usize, usize)>,
+    failure: Vec<(ObjectHandle, ObjectHandle, Error)>,
+}
+
+impl<S> World<S> where S: Scalar + 'static {
+    pub fn new(broadphase_:Box<dyn Broadphase<S>>) -> World<S> {
+        World {slot:Vec::new(), free:Vec::new(), broadphase:broadphase_, cache:CoherentCache::new(), aabb:Vec::new(), pair:Vec::new(), failure:Vec::new()}
+    }
+
+    pub fn add(&mut self, object_:Object<S>) -> ObjectHandle {
+        match self.free.pop() {
+            Some(index) => {
+                let s = &mut self.slot[index];
+                s.object = Some(object_);
+                ObjectHandle {index, generation:s.generation}
+            },
+            None => {
+                self.slot.push(Slot {generation:0, object:Some(object_)});
+                ObjectHandle {index:self.slot.len() - 1, generation:0}
+            },
+        }
+    }
+
+    /// Takes the object out of the world, `None` when the handle is stale.
+    pub fn remove(&mut self, handle_:ObjectHandle) -> Option<Object<S>> {
+        self.get(handle_)?;
+        let s = &mut self.slot[handle_.index];
+        s.generation = s.generation.wrapping_add(1);
+        self.free.push(handle_.index);
+        self.cache.remove_object(handle_.index);
+        s.object.take()
+    }
+
+    pub fn get(&self, handle_:ObjectHandle) -> Option<&Object<S>> {
+        self.slot.get(handle_.index).filter(|s| s.generation == handle_.generation).and_then(|s| s.object.as_ref())
+    }
+
+    /// the object to update in place, its shape included
+    pub fn get_mut(&mut self, handle_:ObjectHandle) -> Option<&mut Object<S>> {
+        self.slot.get_mut(handle_.index).filter(|s| s.generation == handle_.generation).and_then(|s| s.object.as_mut())
+    }
+
+    /// Moves the object to `pose_`, returns false when the handle is stale.
+    pub fn set_pose(&mut self, handle_:ObjectHandle, pose_:Isometry<S>) -> bool {
+        self.get_mut(handle_).map(|o| o.pose = pose_).is_some()
+    }
+
+    /// Sets the velocities `step` moves the object by, returns false when the handle is stale.
+    pub fn set_velocity(&mut self, handle_:ObjectHandle, linear_:Float3<S>, angular_:Float3<S>) -> bool {
+        self.get_mut(handle_).map(|o| {
+            o.linear = linear_;
+            o.angular = angular_;
+        }).is_some()
+    }
+
+    /// handles of the objects in the world, by slot
+    pub fn handles(&self) -> impl Iterator<Item = ObjectHandle> + '_ {
+        self.slot.iter().enumerate()
+            .filter(|(_, s)| s.object.is_some())
+            .map(|(index, s)| ObjectHandle {index, generation:s.generation})
+    }
+
+    pub fn len(&self) -> usize { self.slot.len() - self.free.len() }
+
+    pub fn is_empty(&self) -> bool { self.len() == 0 }
+
+    /// Advances every object by `dt_` along its velocities, then runs `detect`.
+    pub fn step(&mut self, dt_:S) -> Vec<Collision<S>> {
+        for o in self.slot.iter_mut().filter_map(|s| s.object.as_mut()) {
+            o.pose = Motion::new(o.pose, o.linear, o.angular).pose_at(dt_);
+        }
+        self.detect()
+    }
+
+    /// Every pair of objects that touch or overlap, ordered by the slots of `a` then `b`.
+    /// Pairs the narrowphase fails on are left out and listed by `failures` instead.
+    pub fn detect(&mut self) -> Vec<Collision<S>> {
+        self.failure.clear();
+        self.aabb.clear();
+        self.aabb.extend(self.slot.iter().map(|s| s.object.as_ref().map_or(Aabb::empty(), |o| Aabb::from_support(&o.posed()))));
+        self.broadphase.pairs(&self.aabb, &mut self.pair);
+        self.cache.retain(&self.pair);
+        let mut ret = Vec::new();
+        for &(i, j) in &self.pair {
+            let (Some(a), Some(b)) = (&self.slot[i].object, &self.slot[j].object) else { continue };
+            let ab = self.aabb[i].center() - self.aabb[j].center();
+            let (ha, hb) = (ObjectHandle {index:i, generation:self.slot[i].generation}, ObjectHandle {index:j, generation:self.slot[j].generation});
+            match gjk3d::check_coherent(&a.posed(), &b.posed(), &ab, self.cache.frame((i, j))) {
+                Ok(r) if r.is_colliding() => ret.push(Collision {a:ha, b:hb, result:r}),
+                Ok(_) => {},
+                Err(e) => self.failure.push((ha, hb, e)),
+            }
+        }
+        ret
+    }
+
+    /// pairs the last `detect` could not decide on, with the error of each
+    pub fn failures(&self) -> &[(ObjectHandle, ObjectHandle, Error)] {
+        &self.failure
+    }
+}
+
+impl<S> Default for World<S> where S: Scalar + 'static {
+    fn default() -> Self { World::new(Box::new(DynamicTree::default())) }
+}
+
+// endregion
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shape::Sphere;
+
+    #[test]
+    fn failed_pairs_do_not_hide_others() {
+        let mut world = World::default();
+        let sphere = |x:f32| Sphere {center:Float3 {x, y:0.0, z:0.0}, radius:1.0};
+        let a = world.add(Object::new(sphere(0.0), Isometry::identity()));
+        let b = world.add(Object::new(sphere(1.5), Isometry::identity()));
+        //three points span no volume, the narrowphase rejects the shape
+        let flat = vec![Float3 {x:0.0, y:0.0, z:0.0}, Float3 {x:1.0, y:0.0, z:0.0}, Float3 {x:0.0, y:1.0, z:0.0}];
+        let c = world.add(Object::new(flat, Isometry::identity()));
+        let collision = world.detect();
+        assert_eq!(collision.len(), 1);
+        assert_eq!((collision[0].a, collision[0].b), (a, b));
+        let failure: Vec<_> = world.failures().iter().map(|(x, y, e)| (*x, *y, matches!(e, Error::InvalidInput))).collect();
+        assert_eq!(failure, vec![(a, c, true), (b, c, true)]);
+        //the next query starts over
+        world.remove(c);
+        assert_eq!(world.detect().len(), 1);
+        assert!(world.failures().is_empty());
+    }
+
+    #[test]
+    fn stale_handle_after_reuse() {
+        let mut world = World::default();
+        let sphere = |x:f32| Sphere {center:Float3 {x, y:0.0, z:0.0}, radius:1.0};
+        let a = world.add(Object::new(sphere(0.0), Isometry::identity()));
+        let b = world.add(Object::new(sphere(1.5), Isometry::identity()));
+        assert_eq!(world.detect().len(), 1);
+        assert!(world.remove(b).is_some());
+        //the new object takes the slot of `b`
+        let c = world.add(Object::new(sphere(-1.5), Isometry::identity()));
+        assert_eq!(c.index(), b.index());
+        assert_ne!(c, b);
+        assert!(world.get(b).is_none());
+        assert!(world.get_mut(b).is_none());
+        assert!(!world.set_pose(b, Isometry::from_translation(Float3 {x:10.0, y:0.0, z:0.0})));
+        assert!(!world.set_velocity(b, Float3 {x:1.0, y:0.0, z:0.0}, Float3::zero()));
+        assert!(world.remove(b).is_none());
+        //none of the calls above touched `c`
+        assert_eq!(world.len(), 2);
+        assert_eq!(world.handles().collect::<Vec<_>>(), vec![a, c]);
+        let collision = world.detect();
+        assert_eq!(collision.len(), 1);
+        assert_eq!((collision[0].a, collision[0].b), (a, c));
+        //B sits at -x of A, so the normal points from A towards -x
+        assert!(collision[0].result.normal.x < 0.0);
+    }
+}